@@ -1,38 +1,36 @@
 use ash::extensions::{ext, khr};
-use ash::version::{DeviceV1_0, EntryV1_0, InstanceV1_0};
 use ash::vk;
 use log::LevelFilter;
 use raw_window_handle::HasRawWindowHandle;
 use std::error::Error;
 use std::ffi::CStr;
 use std::fmt;
-use std::ops::DerefMut;
-use std::os::raw::c_void;
-use vk_raii::buffer::Buffer;
+use vk_raii::buffer::{Buffer, BufferDesc};
 use vk_raii::command_buffer::CommandBuffers;
-use vk_raii::command_pool::CommandPool;
-use vk_raii::debug_report::{Callback, DebugMessanger, RawDebugMessenger};
-use vk_raii::descr_pool::DescriptorPool;
+use vk_raii::command_pool::{CommandPool, CommandPoolDesc};
+use vk_raii::debug_report::{Callback, DebugMessenger};
+use vk_raii::descr_pool::{DescriptorPool, DescriptorPoolDesc};
 use vk_raii::descr_set::DescriptorSets;
-use vk_raii::device::Device;
-use vk_raii::ds_layout::DescriptorSetLayout;
+use vk_raii::device::{Device, DeviceBuilder, QueueCapability};
+use vk_raii::ds_layout::{DescriptorBinding, DescriptorSetLayout};
+use vk_raii::event::Event;
 use vk_raii::fence::Fence;
-use vk_raii::instance::Instance;
-use vk_raii::memory::Memory;
-use vk_raii::pipeline::Pipeline;
+use vk_raii::framebuffer::{Framebuffer, FramebufferDesc};
+use vk_raii::image_view::{ImageView, ImageViewDesc};
+use vk_raii::instance::{CreationMessenger, Instance, InstanceBuilder};
+use vk_raii::memory::{Memory, MemoryDesc};
+use vk_raii::physical_device::DeviceSelector;
+use vk_raii::pipeline::{Pipeline, Specialization};
 use vk_raii::pipeline_cache::PipelineCache;
 use vk_raii::pipeline_layout::PipelineLayout;
 use vk_raii::queue::Queue;
-use vk_raii::render_pass::RenderPass;
-use vk_raii::sampler::Sampler;
+use vk_raii::render_pass::{RenderPass, RenderPassDesc, SubpassDesc};
+use vk_raii::sampler::{Sampler, SamplerDesc};
+use vk_raii::semaphore::Semaphore;
 use vk_raii::shader_module::ShaderModule;
 use vk_raii::surface::Surface;
-use vk_raii::swapchain::Swapchain;
-use vk_raii::{
-    buffer, command_buffer, command_pool, debug_report, descr_pool, descr_set, device, ds_layout,
-    fence, instance, memory, pipeline, pipeline_cache, pipeline_layout, queue, render_pass,
-    sampler, shader_module, surface, swapchain,
-};
+use vk_raii::swapchain::{Swapchain, SwapchainDesc};
+use vk_raii::{image, surface};
 
 fn main() {
     env_logger::builder()
@@ -47,189 +45,123 @@ fn init_vulkan() -> Result<String, InitVulkanError> {
     let entry = unsafe { ash::Entry::new() }.map_err(|e| init_err("entry", e))?;
     let instance = init_instance(entry)?;
     let _debug_report = init_debug_messenger(instance.clone())?;
-    let device = create_device(instance.clone())?;
+
+    let events_loop = winit::event_loop::EventLoop::new();
+    let window = winit::window::WindowBuilder::new()
+        .with_inner_size(winit::dpi::PhysicalSize::new(800, 600))
+        .build(&events_loop)
+        .map_err(|e| init_err("winit window", e))?;
+    let surface = create_surface(instance.clone(), &window)?;
+
+    let (device, present_queue) = create_device(instance, &surface)?;
     let _buffer = create_buffer(device.clone())?;
     let _memory = allocate_memory(device.clone())?;
-    let queue = get_queue(device.clone());
     let command_pool = create_command_pool(device.clone())?;
-    let _command_buffers = allocate_command_buffers(device.clone(), command_pool)?;
+    let _command_buffers = allocate_command_buffers(command_pool)?;
     let samplers = create_samplers(device.clone())?;
     let descr_set_layout = create_descr_set_layout(device.clone(), samplers)?;
     let pipeline_layout = create_pipeline_layout(device.clone(), vec![descr_set_layout.clone()])?;
-    let _pipeline_cache = create_pipeline_cache(device.clone())?;
+    let pipeline_cache = create_pipeline_cache(device.clone())?;
     let compute_shader = create_compute_shader(device.clone())?;
     let _compute_pipeline =
-        create_compute_pipeline(device.clone(), pipeline_layout, compute_shader)?;
-    let _render_pass = create_render_pass(device.clone())?;
+        create_compute_pipeline(pipeline_layout, compute_shader, &pipeline_cache)?;
+    let render_pass = create_render_pass(device.clone())?;
+    let _framebuffer = create_framebuffer(render_pass)?;
     let descr_pool = create_descriptor_pool(device.clone())?;
-    let _descr_sets = create_descriptor_sets(device.clone(), descr_pool, descr_set_layout)?;
-    let _fence = create_fence(device);
+    let _descr_sets = create_descriptor_sets(descr_pool, descr_set_layout)?;
+    let _fence = create_fence(device.clone())?;
+    let _semaphore = create_semaphore(device.clone())?;
+    let _event = create_event(device)?;
 
-    let events_loop = winit::event_loop::EventLoop::new();
-    let window = winit::window::WindowBuilder::new()
-        .with_inner_size(winit::dpi::PhysicalSize::new(800, 600))
-        .build(&events_loop)
-        .map_err(|e| init_err("winit window", e))?;
-    let surface = create_surface(instance, &window)?;
-    let _swapchain = create_swapchain(queue, surface)?;
+    let swapchain = create_swapchain(present_queue, surface)?;
+    let _swapchain_views = create_swapchain_image_views(&swapchain)?;
 
     Ok("Success".into())
 }
 
 fn init_instance(entry: ash::Entry) -> Result<Instance, InitVulkanError> {
-    let app_inf = vk::ApplicationInfo::builder().api_version(vk::make_version(1, 0, 0));
     let surface_exts = surface::enumerate_surface_extensions(&entry)
         .map_err(|e| init_err("enumerate surface extensions", e))?;
 
     log::trace!("Surface extensions: {:?}", surface_exts);
 
-    let mut exts = vec![ext::DebugUtils::name().as_ptr()];
-    exts.extend(surface_exts.iter().map(|ext| ext.as_ptr() as *const i8));
-    let layers = ["VK_LAYER_KHRONOS_validation\0".as_ptr() as *const i8];
-
-    let ci = vk::InstanceCreateInfo::builder()
-        .application_info(&app_inf)
-        .enabled_layer_names(&layers)
-        .enabled_extension_names(&exts);
-    unsafe {
-        let raw = entry
-            .create_instance(&ci, None)
-            .map_err(|e| init_err("instance", e))?;
-        Ok(Instance::new(raw, instance::Deps { entry }))
-    }
-}
-
-fn init_debug_messenger(instance: Instance) -> Result<DebugMessanger<Callback>, InitVulkanError> {
-    let mut callback = Box::new(Callback::log_reports());
-    let cb_ref = callback.deref_mut();
-    let cb_ptr: *mut Callback = cb_ref;
-
-    let flags = vk::DebugUtilsMessengerCreateFlagsEXT::default();
-    let ci = vk::DebugUtilsMessengerCreateInfoEXT::builder()
-        .message_severity(vk::DebugUtilsMessageSeverityFlagsEXT::all())
-        .message_type(vk::DebugUtilsMessageTypeFlagsEXT::all())
-        .user_data(cb_ptr as *mut c_void)
-        .pfn_user_callback(Some(debug_report::debug_report_with_default_callback))
-        .flags(flags);
-
-    let debug_utils = ext::DebugUtils::new(&instance.dependencies().entry, instance.handle());
-    let raw = unsafe { debug_utils.create_debug_utils_messenger(&ci, None) }
-        .map_err(|e| init_err("debug messenger", e))?;
-
-    let deps = debug_report::Deps {
-        instance,
-        user_data: Some(callback),
+    let validation = CStr::from_bytes_with_nul(b"VK_LAYER_KHRONOS_validation\0").unwrap();
+    let messenger = CreationMessenger {
+        severity: vk::DebugUtilsMessageSeverityFlagsEXT::all(),
+        types: vk::DebugUtilsMessageTypeFlagsEXT::all(),
+        callback: Callback::log_reports(),
     };
 
-    let raw_debug_report = RawDebugMessenger::new(raw, debug_utils);
-
-    unsafe { Ok(DebugMessanger::new(raw_debug_report, deps)) }
+    InstanceBuilder::new()
+        .api_version(vk::API_VERSION_1_0, vk::API_VERSION_1_2)
+        .optional_layer(validation)
+        .require_extension(ext::DebugUtils::name())
+        .require_extensions(&surface_exts)
+        .creation_messenger(messenger)
+        .build(entry)
+        .map_err(|e| init_err("instance", e))
 }
 
-fn create_device(instance: Instance) -> Result<Device, InitVulkanError> {
-    let pdevices =
-        unsafe { instance.enumerate_physical_devices() }.map_err(|e| init_err("pdevices", e))?;
-    let pdevice = match pdevices.get(0) {
-        Some(pd) => Ok(*pd),
-        None => Err(InitVulkanError {
-            msg: "Can't find vulkan pdevice".into(),
-        }),
-    }?;
-
-    let features = vk::PhysicalDeviceFeatures::default();
-    let prioreties = [1f32];
-    let queue = vk::DeviceQueueCreateInfo::builder()
-        .queue_family_index(0)
-        .queue_priorities(&prioreties)
-        .build();
-    let queues_info = [queue];
-
-    let exts = [khr::Swapchain::name().as_ptr()];
-    let ci = vk::DeviceCreateInfo::builder()
-        .queue_create_infos(&queues_info)
-        .enabled_features(&features)
-        .enabled_extension_names(&exts);
-    unsafe {
-        let raw = instance
-            .create_device(pdevice, &ci, None)
-            .map_err(|e| init_err("device", e))?;
-
-        Ok(Device::new(raw, device::Deps { pdevice, instance }))
-    }
+fn init_debug_messenger(instance: Instance) -> Result<DebugMessenger, InitVulkanError> {
+    DebugMessenger::create(
+        &instance,
+        vk::DebugUtilsMessageSeverityFlagsEXT::all(),
+        vk::DebugUtilsMessageTypeFlagsEXT::all(),
+        Callback::log_reports(),
+    )
+    .map_err(|e| init_err("debug messenger", e))
 }
 
-fn get_queue(device: Device) -> Queue {
-    unsafe {
-        let family_index = 0;
-        let queue_index = 0;
-        let raw = device.get_device_queue(family_index, queue_index);
-        Queue::new(
-            raw,
-            queue::Deps {
-                device,
-                queue_index,
-                family_index,
-            },
-        )
+fn create_device(
+    instance: Instance,
+    surface: &Surface,
+) -> Result<(Device, Queue), InitVulkanError> {
+    let selection = DeviceSelector::new()
+        .require_extension(khr::Swapchain::name())
+        .require_queue(vk::QueueFlags::GRAPHICS)
+        .require_present(surface)
+        .select(&instance)
+        .map_err(|e| init_err("pdevice", e))?;
+
+    for rejected in &selection.rejected {
+        log::info!("Physical device rejected: {}", rejected);
     }
+
+    let (device, queues) = DeviceBuilder::new(&selection.device)
+        .extension(khr::Swapchain::name())
+        .queue(QueueCapability::Graphics, 1.0)
+        .queue(QueueCapability::Present(surface.clone()), 1.0)
+        .build()
+        .map_err(|e| init_err("device", e))?;
+
+    Ok((device, queues[1].clone()))
 }
 
 fn create_buffer(device: Device) -> Result<Buffer, InitVulkanError> {
-    let queue_family_indices = [0u32];
-    let ci = vk::BufferCreateInfo::builder()
-        .size(128)
-        .usage(vk::BufferUsageFlags::UNIFORM_BUFFER)
-        .sharing_mode(vk::SharingMode::EXCLUSIVE)
-        .queue_family_indices(&queue_family_indices);
-
-    unsafe {
-        let raw = device
-            .create_buffer(&ci, None)
-            .map_err(|e| init_err("buffer", e))?;
-        Ok(Buffer::new(raw, buffer::Deps { device }))
-    }
+    let desc = BufferDesc {
+        size: 128,
+        usage: vk::BufferUsageFlags::UNIFORM_BUFFER,
+        ..Default::default()
+    };
+    Buffer::create(&device, &desc).map_err(|e| init_err("buffer", e))
 }
 
 fn allocate_memory(device: Device) -> Result<Memory, InitVulkanError> {
-    let ai = vk::MemoryAllocateInfo::builder()
-        .allocation_size(128)
-        .memory_type_index(0);
-
-    unsafe {
-        let raw = device
-            .allocate_memory(&ai, None)
-            .map_err(|e| init_err("memory", e))?;
-        Ok(Memory::new(raw, memory::Deps { device }))
-    }
+    let desc = MemoryDesc {
+        size: 128,
+        memory_type_index: 0,
+    };
+    Memory::allocate(&device, &desc).map_err(|e| init_err("memory", e))
 }
 
 fn create_command_pool(device: Device) -> Result<CommandPool, InitVulkanError> {
-    let ci = vk::CommandPoolCreateInfo::builder().queue_family_index(0);
-
-    unsafe {
-        let raw = device
-            .create_command_pool(&ci, None)
-            .map_err(|e| init_err("buffer", e))?;
-        Ok(CommandPool::new(raw, command_pool::Deps { device }))
-    }
+    let desc = CommandPoolDesc::default();
+    CommandPool::create(&device, &desc).map_err(|e| init_err("command pool", e))
 }
 
-fn allocate_command_buffers(
-    device: Device,
-    pool: CommandPool,
-) -> Result<CommandBuffers, InitVulkanError> {
-    let ci = vk::CommandBufferAllocateInfo::builder()
-        .command_pool(*pool)
-        .level(vk::CommandBufferLevel::PRIMARY)
-        .command_buffer_count(5);
-
-    let cbs = unsafe {
-        let raw = device
-            .allocate_command_buffers(&ci)
-            .map_err(|e| init_err("command buffers", e))?;
-        let deps = command_buffer::Deps { pool };
-        Ok(CommandBuffers::new(raw, deps))?
-    };
+fn allocate_command_buffers(pool: CommandPool) -> Result<CommandBuffers, InitVulkanError> {
+    let cbs = CommandBuffers::allocate(&pool, vk::CommandBufferLevel::PRIMARY, 5)
+        .map_err(|e| init_err("command buffers", e))?;
 
     let (raw, deps) = cbs
         .try_unwrap()
@@ -242,70 +174,43 @@ fn create_descr_set_layout(
     device: Device,
     samplers: Vec<Sampler>,
 ) -> Result<DescriptorSetLayout, InitVulkanError> {
-    let uniform_binding = vk::DescriptorSetLayoutBinding::builder()
-        .binding(0)
-        .descriptor_count(1)
-        .descriptor_type(vk::DescriptorType::UNIFORM_BUFFER);
-
-    let storage_binding = vk::DescriptorSetLayoutBinding::builder()
-        .binding(1)
-        .descriptor_count(1)
-        .descriptor_type(vk::DescriptorType::STORAGE_BUFFER);
-
-    let raw_samplers: Vec<vk::Sampler> = samplers.iter().map(|s| s.handle()).copied().collect();
-
-    let samplers_binding = vk::DescriptorSetLayoutBinding::builder()
-        .binding(2)
-        .descriptor_count(2)
-        .descriptor_type(vk::DescriptorType::COMBINED_IMAGE_SAMPLER)
-        .immutable_samplers(raw_samplers.as_slice());
-
     let bindings = [
-        uniform_binding.build(),
-        storage_binding.build(),
-        samplers_binding.build(),
+        DescriptorBinding {
+            binding: 0,
+            descriptor_count: 1,
+            descriptor_type: vk::DescriptorType::UNIFORM_BUFFER,
+            ..Default::default()
+        },
+        DescriptorBinding {
+            binding: 1,
+            descriptor_count: 1,
+            descriptor_type: vk::DescriptorType::STORAGE_BUFFER,
+            ..Default::default()
+        },
+        DescriptorBinding {
+            binding: 2,
+            descriptor_count: 2,
+            descriptor_type: vk::DescriptorType::COMBINED_IMAGE_SAMPLER,
+            immutable_samplers: samplers,
+            ..Default::default()
+        },
     ];
 
-    let ci = vk::DescriptorSetLayoutCreateInfo::builder().bindings(&bindings);
-
-    unsafe {
-        let raw = device
-            .create_descriptor_set_layout(&ci, None)
-            .map_err(|e| init_err("descriptor set layout ", e))?;
-        let deps = ds_layout::Deps { device, samplers };
-        Ok(DescriptorSetLayout::new(raw, deps))
-    }
+    DescriptorSetLayout::create(&device, &bindings)
+        .map_err(|e| init_err("descriptor set layout", e))
 }
 
 fn create_samplers(device: Device) -> Result<Vec<Sampler>, InitVulkanError> {
-    let ci = vk::SamplerCreateInfo::default();
-    unsafe {
-        let raw1 = device
-            .create_sampler(&ci, None)
-            .map_err(|e| init_err("sampler", e))?;
-
-        let raw2 = device
-            .create_sampler(&ci, None)
-            .map_err(|e| init_err("sampler", e))?;
-
-        Ok(vec![
-            Sampler::new(
-                raw1,
-                sampler::Deps {
-                    device: device.clone(),
-                },
-            ),
-            Sampler::new(raw2, sampler::Deps { device }),
-        ])
-    }
+    let desc = SamplerDesc::default();
+    (0..2)
+        .map(|_| Sampler::create(&device, &desc).map_err(|e| init_err("sampler", e)))
+        .collect()
 }
 
 fn create_pipeline_layout(
     device: Device,
     ds_layouts: Vec<DescriptorSetLayout>,
 ) -> Result<PipelineLayout, InitVulkanError> {
-    let raw_ds_layouts: Vec<vk::DescriptorSetLayout> =
-        ds_layouts.iter().map(|dsl| *dsl.handle()).collect();
     let push_constant1 = vk::PushConstantRange::builder()
         .size(16)
         .offset(0)
@@ -320,49 +225,25 @@ fn create_pipeline_layout(
 
     let ranges = [push_constant1, push_constant2];
 
-    let ci = vk::PipelineLayoutCreateInfo::builder()
-        .set_layouts(raw_ds_layouts.as_slice())
-        .push_constant_ranges(&ranges);
-
-    unsafe {
-        let raw = device
-            .create_pipeline_layout(&ci, None)
-            .map_err(|e| init_err("pipeline layout", e))?;
-        let deps = pipeline_layout::Deps { device, ds_layouts };
-        Ok(PipelineLayout::new(raw, deps))
-    }
+    PipelineLayout::create(&device, &ds_layouts, &ranges)
+        .map_err(|e| init_err("pipeline layout", e))
 }
 
 fn create_pipeline_cache(device: Device) -> Result<PipelineCache, InitVulkanError> {
-    let ci = vk::PipelineCacheCreateInfo::default();
-    unsafe {
-        let raw = device
-            .create_pipeline_cache(&ci, None)
-            .map_err(|e| init_err("sampler", e))?;
-
-        Ok(PipelineCache::new(raw, pipeline_cache::Deps { device }))
-    }
+    PipelineCache::create(&device, &[]).map_err(|e| init_err("pipeline cache", e))
 }
 
 fn create_compute_shader(device: Device) -> Result<ShaderModule, InitVulkanError> {
     let mut file = std::fs::File::open("examples/data/compute_test.comp.spv").unwrap();
     let data = ash::util::read_spv(&mut file).unwrap();
 
-    let ci = vk::ShaderModuleCreateInfo::builder().code(&data);
-
-    unsafe {
-        let raw = device
-            .create_shader_module(&ci, None)
-            .map_err(|e| init_err("compute shader", e))?;
-        let deps = shader_module::Deps { device };
-        Ok(ShaderModule::new(raw, deps))
-    }
+    ShaderModule::create(&device, &data).map_err(|e| init_err("compute shader", e))
 }
 
 fn create_compute_pipeline(
-    device: Device,
     layout: PipelineLayout,
     shader: ShaderModule,
+    cache: &PipelineCache,
 ) -> Result<Pipeline, InitVulkanError> {
     let map_entries = [
         vk::SpecializationMapEntry::builder()
@@ -379,50 +260,40 @@ fn create_compute_pipeline(
 
     let spec_data = [0u8; 32];
 
-    let spec_info = vk::SpecializationInfo::builder()
-        .data(&spec_data)
-        .map_entries(&map_entries);
-
-    let stage_ci = vk::PipelineShaderStageCreateInfo::builder()
-        .stage(vk::ShaderStageFlags::COMPUTE)
-        .module(*shader.handle())
-        .specialization_info(&spec_info)
-        .name(&CStr::from_bytes_with_nul(b"main\0").unwrap());
-
-    let ci = vk::ComputePipelineCreateInfo::builder()
-        .base_pipeline_index(-1)
-        .layout(*layout.handle())
-        .stage(stage_ci.build());
-
-    unsafe {
-        let raw = device
-            .create_compute_pipelines(Default::default(), &[ci.build()], None)
-            .map_err(|e| init_err("compute pipeline", e.1))?
-            .remove(0);
-
-        let deps = pipeline::Deps { layout };
+    let specialization = Specialization {
+        map_entries: &map_entries,
+        data: &spec_data,
+    };
 
-        Ok(Pipeline::new(raw, deps))
-    }
+    let entry_point = CStr::from_bytes_with_nul(b"main\0").unwrap();
+    Pipeline::create_compute(
+        &layout,
+        &shader,
+        entry_point,
+        Some(specialization),
+        Some(cache),
+    )
+    .map_err(|e| init_err("compute pipeline", e))
 }
 
 fn create_render_pass(device: Device) -> Result<RenderPass, InitVulkanError> {
-    let attachments = [];
-
-    let subpass_descr = vk::SubpassDescription::builder();
+    let desc = RenderPassDesc {
+        subpasses: vec![SubpassDesc::default()],
+        ..Default::default()
+    };
 
-    let subpasses = [subpass_descr.build()];
+    RenderPass::create(&device, &desc).map_err(|e| init_err("render pass", e))
+}
 
-    let ci = vk::RenderPassCreateInfo::builder()
-        .attachments(&attachments)
-        .subpasses(&subpasses);
+fn create_framebuffer(render_pass: RenderPass) -> Result<Framebuffer, InitVulkanError> {
+    let desc = FramebufferDesc {
+        width: 800,
+        height: 600,
+        layers: 1,
+        ..Default::default()
+    };
 
-    unsafe {
-        let raw = device
-            .create_render_pass(&ci, None)
-            .map_err(|e| init_err("render pass", e))?;
-        Ok(RenderPass::new(raw, render_pass::Deps { device }))
-    }
+    Framebuffer::create(&render_pass, Vec::new(), &desc).map_err(|e| init_err("framebuffer", e))
 }
 
 fn create_descriptor_pool(device: Device) -> Result<DescriptorPool, InitVulkanError> {
@@ -441,52 +312,38 @@ fn create_descriptor_pool(device: Device) -> Result<DescriptorPool, InitVulkanEr
         .descriptor_count(3)
         .build();
 
-    let pool_sizes = [pool_size_1, pool_size_2, pool_size_3];
-
-    let ci = vk::DescriptorPoolCreateInfo::builder()
-        .max_sets(10)
-        .pool_sizes(&pool_sizes);
+    let desc = DescriptorPoolDesc {
+        max_sets: 10,
+        pool_sizes: vec![pool_size_1, pool_size_2, pool_size_3],
+        ..Default::default()
+    };
 
-    unsafe {
-        let raw = device
-            .create_descriptor_pool(&ci, None)
-            .map_err(|e| init_err("descriptor pool", e))?;
-        Ok(DescriptorPool::new(raw, descr_pool::Deps { device }))
-    }
+    DescriptorPool::create(&device, &desc).map_err(|e| init_err("descriptor pool", e))
 }
 
 fn create_descriptor_sets(
-    device: Device,
     descr_pool: DescriptorPool,
     ds_layout: DescriptorSetLayout,
 ) -> Result<DescriptorSets, InitVulkanError> {
-    let ds_layouts = [*ds_layout];
-    let ai = vk::DescriptorSetAllocateInfo::builder()
-        .set_layouts(&ds_layouts)
-        .descriptor_pool(*descr_pool);
-
-    unsafe {
-        let raw = device
-            .allocate_descriptor_sets(&ai)
-            .map_err(|e| init_err("descriptor sets", e))?;
-        let deps = descr_set::Deps {
-            pool: descr_pool,
-            ds_layouts: vec![ds_layout],
-            can_free: false,
-        };
-        Ok(DescriptorSets::new(raw, deps))
-    }
+    DescriptorSets::allocate(&descr_pool, &[ds_layout]).map_err(|e| init_err("descriptor sets", e))
 }
 
 fn create_fence(device: Device) -> Result<Fence, InitVulkanError> {
-    let ci = vk::FenceCreateInfo::builder();
+    let fence = Fence::create(&device, true).map_err(|e| init_err("fence", e))?;
+    fence
+        .wait(u64::MAX)
+        .map_err(|e| init_err("fence wait", e))?;
+    Ok(fence)
+}
 
-    unsafe {
-        let raw = device
-            .create_fence(&ci, None)
-            .map_err(|e| init_err("render pass", e))?;
-        Ok(Fence::new(raw, fence::Deps { device }))
-    }
+fn create_semaphore(device: Device) -> Result<Semaphore, InitVulkanError> {
+    Semaphore::create(&device).map_err(|e| init_err("semaphore", e))
+}
+
+fn create_event(device: Device) -> Result<Event, InitVulkanError> {
+    let event = Event::create(&device).map_err(|e| init_err("event", e))?;
+    event.set().map_err(|e| init_err("event set", e))?;
+    Ok(event)
 }
 
 fn create_surface(
@@ -504,16 +361,14 @@ fn create_surface(
 }
 
 fn create_swapchain(queue: Queue, surface: Surface) -> Result<Swapchain, InitVulkanError> {
-    let instance = &surface.dependencies().instance;
     let device = queue.dependencies().device.clone();
     let pdevice = device.dependencies().pdevice;
     let surface_loader = &surface.dependencies().loader;
-    let loader = khr::Swapchain::new(instance.handle(), device.handle());
-    let queue_family_indices = [queue.dependencies().family_index];
+    let family_index = queue.dependencies().family_index;
 
     let surface_supported = unsafe {
         surface_loader
-            .get_physical_device_surface_support(pdevice, queue_family_indices[0], *surface)
+            .get_physical_device_surface_support(pdevice, family_index, *surface)
             .map_err(|e| init_err("surface support", e))?
     };
 
@@ -538,34 +393,43 @@ fn create_swapchain(queue: Queue, surface: Surface) -> Result<Swapchain, InitVul
             .current_transform
     };
 
-    let ci = vk::SwapchainCreateInfoKHR::builder()
-        .surface(*surface)
-        .min_image_count(3)
-        .image_extent(vk::Extent2D {
+    let desc = SwapchainDesc {
+        min_image_count: 3,
+        image_format: format.format,
+        image_color_space: format.color_space,
+        image_extent: vk::Extent2D {
             width: 800,
             height: 600,
-        })
-        .image_format(format.format)
-        .image_array_layers(1)
-        .image_sharing_mode(vk::SharingMode::EXCLUSIVE)
-        .queue_family_indices(&queue_family_indices)
-        .present_mode(vk::PresentModeKHR::FIFO)
-        .pre_transform(transform)
-        .composite_alpha(vk::CompositeAlphaFlagsKHR::OPAQUE)
-        .image_usage(vk::ImageUsageFlags::COLOR_ATTACHMENT)
-        .image_color_space(format.color_space);
+        },
+        image_array_layers: 1,
+        image_usage: vk::ImageUsageFlags::COLOR_ATTACHMENT,
+        pre_transform: transform,
+        composite_alpha: vk::CompositeAlphaFlagsKHR::OPAQUE,
+        present_mode: vk::PresentModeKHR::FIFO,
+        ..Default::default()
+    };
 
-    unsafe {
-        let raw = loader
-            .create_swapchain(&ci, None)
-            .map_err(|e| init_err("swapchain", e))?;
-        let deps = swapchain::Deps {
-            loader,
-            device,
-            surface,
-        };
-        Ok(Swapchain::new(raw, deps))
-    }
+    Swapchain::create(&device, &surface, &desc).map_err(|e| init_err("swapchain", e))
+}
+
+fn create_swapchain_image_views(swapchain: &Swapchain) -> Result<Vec<ImageView>, InitVulkanError> {
+    let images = image::swapchain_images(swapchain).map_err(|e| init_err("swapchain images", e))?;
+
+    let desc = ImageViewDesc {
+        view_type: vk::ImageViewType::TYPE_2D,
+        format: swapchain.desc().image_format,
+        subresource_range: vk::ImageSubresourceRange::builder()
+            .aspect_mask(vk::ImageAspectFlags::COLOR)
+            .level_count(1)
+            .layer_count(1)
+            .build(),
+        ..Default::default()
+    };
+
+    images
+        .into_iter()
+        .map(|image| ImageView::create(image, &desc).map_err(|e| init_err("image view", e)))
+        .collect()
 }
 
 #[derive(Debug)]
@@ -1,10 +1,31 @@
 use crate::device::Device;
-use crate::{Handle, RawHandle};
+use crate::{Handle, RawHandle, Sharing};
+use ash::prelude::VkResult;
 use ash::version::DeviceV1_0;
 use ash::vk;
 
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct BufferDesc {
+    pub flags: vk::BufferCreateFlags,
+    pub size: vk::DeviceSize,
+    pub usage: vk::BufferUsageFlags,
+    pub sharing: Sharing,
+}
+
+impl BufferDesc {
+    pub fn create_info(&self) -> vk::BufferCreateInfoBuilder<'_> {
+        vk::BufferCreateInfo::builder()
+            .flags(self.flags)
+            .size(self.size)
+            .usage(self.usage)
+            .sharing_mode(self.sharing.mode())
+            .queue_family_indices(self.sharing.queue_family_indices())
+    }
+}
+
 pub struct Deps {
     pub device: Device,
+    pub desc: BufferDesc,
 }
 
 impl RawHandle for vk::Buffer {
@@ -14,9 +35,38 @@ impl RawHandle for vk::Buffer {
         "buffer"
     }
 
+    fn device(deps: &Self::Dependencies) -> Option<&Device> {
+        Some(&deps.device)
+    }
+
+    fn object_type() -> vk::ObjectType {
+        vk::ObjectType::BUFFER
+    }
+
+    fn raw_handles(&self) -> Vec<u64> {
+        vec![vk::Handle::as_raw(*self)]
+    }
+
     fn destroy(&self, dependencies: &Self::Dependencies) {
         unsafe { dependencies.device.destroy_buffer(*self, None) }
     }
 }
 
 pub type Buffer = Handle<vk::Buffer, Deps>;
+
+impl Buffer {
+    pub fn create(device: &Device, desc: &BufferDesc) -> VkResult<Self> {
+        unsafe {
+            let raw = device.create_buffer(&desc.create_info(), None)?;
+            let deps = Deps {
+                device: device.clone(),
+                desc: desc.clone(),
+            };
+            Ok(Self::new(raw, deps))
+        }
+    }
+
+    pub fn desc(&self) -> &BufferDesc {
+        &self.dependencies().desc
+    }
+}
@@ -0,0 +1,128 @@
+use crate::buffer::Buffer;
+use crate::device::Device;
+use crate::{Handle, RawHandle};
+use ash::version::DeviceV1_0;
+use ash::vk;
+use std::error::Error;
+use std::fmt;
+
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash)]
+pub struct BufferViewDesc {
+    pub format: vk::Format,
+    pub offset: vk::DeviceSize,
+    pub range: vk::DeviceSize,
+}
+
+pub struct Deps {
+    pub buffer: Buffer,
+    pub desc: BufferViewDesc,
+}
+
+impl RawHandle for vk::BufferView {
+    type Dependencies = Deps;
+
+    fn name() -> &'static str {
+        "buffer view"
+    }
+
+    fn device(deps: &Self::Dependencies) -> Option<&Device> {
+        Some(&deps.buffer.dependencies().device)
+    }
+
+    fn object_type() -> vk::ObjectType {
+        vk::ObjectType::BUFFER_VIEW
+    }
+
+    fn raw_handles(&self) -> Vec<u64> {
+        vec![vk::Handle::as_raw(*self)]
+    }
+
+    fn destroy(&self, deps: &Self::Dependencies) {
+        let device = &deps.buffer.dependencies().device;
+        unsafe { device.destroy_buffer_view(*self, None) }
+    }
+}
+
+pub type BufferView = Handle<vk::BufferView, Deps>;
+
+impl BufferView {
+    pub fn desc(&self) -> &BufferViewDesc {
+        &self.dependencies().desc
+    }
+
+    /// Creates view of `desc.range` bytes of `buffer`, starting from `desc.offset`.
+    /// `desc.range` may be `vk::WHOLE_SIZE` to view the rest of buffer.
+    pub fn create(buffer: &Buffer, desc: &BufferViewDesc) -> Result<Self, CreateError> {
+        let BufferViewDesc {
+            format,
+            offset,
+            range,
+        } = *desc;
+        let buffer_size = buffer.desc().size;
+        let in_range = match range {
+            vk::WHOLE_SIZE => offset < buffer_size,
+            0 => false,
+            _ => matches!(offset.checked_add(range), Some(end) if end <= buffer_size),
+        };
+
+        if !in_range {
+            return Err(CreateError::OutOfRange {
+                offset,
+                range,
+                buffer_size,
+            });
+        }
+
+        let ci = vk::BufferViewCreateInfo::builder()
+            .buffer(**buffer)
+            .format(format)
+            .offset(offset)
+            .range(range);
+
+        unsafe {
+            let device = &buffer.dependencies().device;
+            let raw = device.create_buffer_view(&ci, None)?;
+            let deps = Deps {
+                buffer: buffer.clone(),
+                desc: *desc,
+            };
+            Ok(Self::new(raw, deps))
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum CreateError {
+    /// Viewed range doesn't fit in buffer.
+    OutOfRange {
+        offset: vk::DeviceSize,
+        range: vk::DeviceSize,
+        buffer_size: vk::DeviceSize,
+    },
+    Vulkan(vk::Result),
+}
+
+impl From<vk::Result> for CreateError {
+    fn from(e: vk::Result) -> Self {
+        Self::Vulkan(e)
+    }
+}
+
+impl Error for CreateError {}
+
+impl fmt::Display for CreateError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CreateError::OutOfRange {
+                offset,
+                range,
+                buffer_size,
+            } => write!(
+                f,
+                "Buffer view range (offset: {}, range: {}) is out of buffer of size {}",
+                offset, range, buffer_size
+            ),
+            CreateError::Vulkan(e) => write!(f, "Can't create buffer view: {}", e),
+        }
+    }
+}
@@ -1,5 +1,7 @@
 use crate::command_pool::CommandPool;
+use crate::device::Device;
 use crate::{Handle, RawHandle};
+use ash::prelude::VkResult;
 use ash::version::DeviceV1_0;
 use ash::vk;
 
@@ -14,14 +16,35 @@ impl RawHandle for vk::CommandBuffer {
         "command buffer"
     }
 
+    fn device(deps: &Self::Dependencies) -> Option<&Device> {
+        Some(&deps.pool.dependencies().device)
+    }
+
+    fn object_type() -> vk::ObjectType {
+        vk::ObjectType::COMMAND_BUFFER
+    }
+
+    fn raw_handles(&self) -> Vec<u64> {
+        vec![vk::Handle::as_raw(*self)]
+    }
+
     fn destroy(&self, deps: &Self::Dependencies) {
-        let device = &deps.pool.dependencies().device;
-        unsafe { device.free_command_buffers(*deps.pool, &[*self]) }
+        let pool_deps = deps.pool.dependencies();
+        let _lock = pool_deps.lock();
+        unsafe { pool_deps.device.free_command_buffers(*deps.pool, &[*self]) }
     }
 }
 
 pub type CommandBuffer = Handle<vk::CommandBuffer, Deps>;
 
+impl CommandBuffer {
+    pub fn allocate(pool: &CommandPool, level: vk::CommandBufferLevel) -> VkResult<Self> {
+        let mut raw = allocate(pool, level, 1)?;
+        let deps = Deps { pool: pool.clone() };
+        unsafe { Ok(Self::new(raw.remove(0), deps)) }
+    }
+}
+
 impl RawHandle for Vec<vk::CommandBuffer> {
     type Dependencies = Deps;
 
@@ -29,12 +52,53 @@ impl RawHandle for Vec<vk::CommandBuffer> {
         "command buffers"
     }
 
+    fn device(deps: &Self::Dependencies) -> Option<&Device> {
+        Some(&deps.pool.dependencies().device)
+    }
+
+    fn object_type() -> vk::ObjectType {
+        vk::ObjectType::COMMAND_BUFFER
+    }
+
+    fn raw_handles(&self) -> Vec<u64> {
+        self.iter().map(|h| vk::Handle::as_raw(*h)).collect()
+    }
+
     fn destroy(&self, deps: &Self::Dependencies) {
+        let pool_deps = deps.pool.dependencies();
+        let _lock = pool_deps.lock();
         unsafe {
-            let device = &deps.pool.dependencies().device;
-            device.free_command_buffers(*deps.pool, self.as_slice())
+            pool_deps
+                .device
+                .free_command_buffers(*deps.pool, self.as_slice())
         }
     }
 }
 
 pub type CommandBuffers = Handle<Vec<vk::CommandBuffer>, Deps>;
+
+impl CommandBuffers {
+    pub fn allocate(
+        pool: &CommandPool,
+        level: vk::CommandBufferLevel,
+        count: u32,
+    ) -> VkResult<Self> {
+        let raw = allocate(pool, level, count)?;
+        let deps = Deps { pool: pool.clone() };
+        unsafe { Ok(Self::new(raw, deps)) }
+    }
+}
+
+fn allocate(
+    pool: &CommandPool,
+    level: vk::CommandBufferLevel,
+    count: u32,
+) -> VkResult<Vec<vk::CommandBuffer>> {
+    let ai = vk::CommandBufferAllocateInfo::builder()
+        .command_pool(**pool)
+        .level(level)
+        .command_buffer_count(count);
+    let deps = pool.dependencies();
+    let _lock = deps.lock();
+    unsafe { deps.device.allocate_command_buffers(&ai) }
+}
@@ -1,10 +1,43 @@
 use crate::device::Device;
 use crate::{Handle, RawHandle};
+use ash::prelude::VkResult;
 use ash::version::DeviceV1_0;
 use ash::vk;
+use std::sync::{Mutex, MutexGuard};
+
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash)]
+pub struct CommandPoolDesc {
+    pub flags: vk::CommandPoolCreateFlags,
+    pub queue_family_index: u32,
+}
+
+impl CommandPoolDesc {
+    pub fn create_info(&self) -> vk::CommandPoolCreateInfoBuilder<'static> {
+        vk::CommandPoolCreateInfo::builder()
+            .flags(self.flags)
+            .queue_family_index(self.queue_family_index)
+    }
+}
 
 pub struct Deps {
     pub device: Device,
+    pub desc: CommandPoolDesc,
+    /// Synchronizes allocation and freeing of command buffers between clones.
+    lock: Mutex<()>,
+}
+
+impl Deps {
+    pub fn new(device: Device, desc: CommandPoolDesc) -> Self {
+        Self {
+            device,
+            desc,
+            lock: Mutex::new(()),
+        }
+    }
+
+    pub(crate) fn lock(&self) -> MutexGuard<'_, ()> {
+        self.lock.lock().unwrap_or_else(|e| e.into_inner())
+    }
 }
 
 impl RawHandle for vk::CommandPool {
@@ -14,9 +47,35 @@ impl RawHandle for vk::CommandPool {
         "command pool"
     }
 
+    fn device(deps: &Self::Dependencies) -> Option<&Device> {
+        Some(&deps.device)
+    }
+
+    fn object_type() -> vk::ObjectType {
+        vk::ObjectType::COMMAND_POOL
+    }
+
+    fn raw_handles(&self) -> Vec<u64> {
+        vec![vk::Handle::as_raw(*self)]
+    }
+
     fn destroy(&self, dependencies: &Self::Dependencies) {
         unsafe { dependencies.device.destroy_command_pool(*self, None) }
     }
 }
 
 pub type CommandPool = Handle<vk::CommandPool, Deps>;
+
+impl CommandPool {
+    pub fn create(device: &Device, desc: &CommandPoolDesc) -> VkResult<Self> {
+        unsafe {
+            let raw = device.create_command_pool(&desc.create_info(), None)?;
+            let deps = Deps::new(device.clone(), *desc);
+            Ok(Self::new(raw, deps))
+        }
+    }
+
+    pub fn desc(&self) -> &CommandPoolDesc {
+        &self.dependencies().desc
+    }
+}
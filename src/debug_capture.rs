@@ -0,0 +1,278 @@
+use crate::debug_report::{Callback, DebugMessage, MessageId};
+use ash::vk;
+use std::fmt::Write;
+use std::process;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+/// Reaction on error-severity message.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum ErrorAction {
+    /// Only collect message.
+    #[default]
+    Collect,
+    /// Collect message and panic on the next check of `MessageCapture`, e.g. `assert_clean`.
+    /// Panic can't unwind through Vulkan, so it's not raised in the callback. Only the first
+    /// error since the previous check is raised.
+    Panic,
+    /// Print message to stderr and abort process.
+    Abort,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct CaptureConfig {
+    pub on_error: ErrorAction,
+    /// Messages with these IDs are not collected, e.g. known false positives.
+    pub ignored: Vec<MessageId>,
+}
+
+/// Messages, collected by `Callback::capture`. Clones share the same buffer.
+///
+/// With `ErrorAction::Panic` asserting methods, `check` and `assert_clean`, panic if error is
+/// collected since the previous check. Queries never panic.
+#[derive(Debug, Clone, Default)]
+pub struct MessageCapture {
+    messages: Arc<Mutex<Vec<DebugMessage>>>,
+    /// First error, not raised yet. Set only with `ErrorAction::Panic`.
+    pending_error: Arc<Mutex<Option<DebugMessage>>>,
+}
+
+impl MessageCapture {
+    /// All collected messages.
+    pub fn messages(&self) -> Vec<DebugMessage> {
+        self.lock().clone()
+    }
+
+    /// # Panics
+    /// * if error is collected with `ErrorAction::Panic` since the previous check.
+    pub fn check(&self) {
+        let error = self
+            .pending_error
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .take();
+        if let Some(msg) = error {
+            panic!("Vulkan validation error: {}", msg)
+        }
+    }
+
+    /// Collected messages of error severity.
+    pub fn errors(&self) -> Vec<DebugMessage> {
+        let severity = vk::DebugUtilsMessageSeverityFlagsEXT::ERROR;
+        self.filtered(|m| m.severity.contains(severity))
+    }
+
+    /// Removes and returns all collected messages.
+    pub fn take_all(&self) -> Vec<DebugMessage> {
+        std::mem::take(&mut *self.lock())
+    }
+
+    /// No warnings or errors are collected.
+    pub fn is_clean(&self) -> bool {
+        self.problems().is_empty()
+    }
+
+    /// # Panics
+    /// * if error is collected with `ErrorAction::Panic` since the previous check.
+    /// * if warnings or errors are collected. Panic message lists them.
+    pub fn assert_clean(&self) {
+        self.check();
+        let problems = self.problems();
+        if problems.is_empty() {
+            return;
+        }
+
+        let mut report = format!("{} validation problems:", problems.len());
+        for msg in &problems {
+            let _ = write!(report, "\n  [{}] {}", msg.level, msg);
+        }
+        panic!("{}", report)
+    }
+
+    fn problems(&self) -> Vec<DebugMessage> {
+        let severity = vk::DebugUtilsMessageSeverityFlagsEXT::ERROR
+            | vk::DebugUtilsMessageSeverityFlagsEXT::WARNING;
+        self.filtered(|m| m.severity.intersects(severity))
+    }
+
+    fn filtered(&self, filter: impl Fn(&DebugMessage) -> bool) -> Vec<DebugMessage> {
+        self.lock().iter().filter(|m| filter(m)).cloned().collect()
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Vec<DebugMessage>> {
+        self.messages.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl Callback {
+    /// Callback, collecting messages into returned `MessageCapture`.
+    pub fn capture(config: CaptureConfig) -> (Self, MessageCapture) {
+        let capture = MessageCapture::default();
+        let messages = capture.clone();
+
+        let callback = move |msg: &DebugMessage| {
+            if config.ignored.iter().any(|id| id.matches(msg)) {
+                return;
+            }
+
+            messages.lock().push(msg.clone());
+
+            if !msg
+                .severity
+                .contains(vk::DebugUtilsMessageSeverityFlagsEXT::ERROR)
+            {
+                return;
+            }
+
+            match config.on_error {
+                ErrorAction::Collect => {}
+                ErrorAction::Panic => {
+                    let mut pending = messages
+                        .pending_error
+                        .lock()
+                        .unwrap_or_else(|e| e.into_inner());
+                    pending.get_or_insert_with(|| msg.clone());
+                }
+                ErrorAction::Abort => {
+                    eprintln!("Vulkan validation error: {}", msg);
+                    process::abort()
+                }
+            }
+        };
+
+        (Self(Box::new(callback)), capture)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::debug_report::MessageLevel;
+    use std::panic::{self, AssertUnwindSafe};
+
+    fn message(name: &str, severity: vk::DebugUtilsMessageSeverityFlagsEXT) -> DebugMessage {
+        let types = vk::DebugUtilsMessageTypeFlagsEXT::VALIDATION;
+        DebugMessage {
+            level: MessageLevel::new(severity, types),
+            severity,
+            types,
+            message_id_name: Some(name.into()),
+            message_id_number: 0,
+            message: format!("{} message", name),
+            queue_labels: Vec::new(),
+            cmd_buf_labels: Vec::new(),
+            objects: Vec::new(),
+        }
+    }
+
+    fn error(name: &str) -> DebugMessage {
+        message(name, vk::DebugUtilsMessageSeverityFlagsEXT::ERROR)
+    }
+
+    fn warning(name: &str) -> DebugMessage {
+        message(name, vk::DebugUtilsMessageSeverityFlagsEXT::WARNING)
+    }
+
+    fn info(name: &str) -> DebugMessage {
+        message(name, vk::DebugUtilsMessageSeverityFlagsEXT::INFO)
+    }
+
+    fn panics(f: impl FnOnce()) -> bool {
+        panic::catch_unwind(AssertUnwindSafe(f)).is_err()
+    }
+
+    #[test]
+    fn ignored_messages_are_not_collected() {
+        let config = CaptureConfig {
+            ignored: vec!["Known".into(), MessageId::Number(7)],
+            ..Default::default()
+        };
+        let (callback, capture) = Callback::capture(config);
+        let mut numbered = warning("Other");
+        numbered.message_id_number = 7;
+
+        (callback.0)(&error("Known"));
+        (callback.0)(&numbered);
+        (callback.0)(&info("Kept"));
+
+        let names: Vec<_> = capture
+            .messages()
+            .into_iter()
+            .map(|m| m.message_id_name)
+            .collect();
+        assert_eq!(names, [Some("Kept".to_string())]);
+        assert!(capture.is_clean());
+    }
+
+    #[test]
+    fn messages_are_counted_by_severity() {
+        let (callback, capture) = Callback::capture(Default::default());
+        (callback.0)(&info("A"));
+        (callback.0)(&warning("B"));
+        (callback.0)(&error("C"));
+        (callback.0)(&error("D"));
+
+        assert_eq!(capture.messages().len(), 4);
+        assert_eq!(capture.errors().len(), 2);
+        assert_eq!(capture.problems().len(), 3);
+        assert!(!capture.is_clean());
+
+        assert_eq!(capture.take_all().len(), 4);
+        assert!(capture.messages().is_empty());
+        assert!(capture.is_clean());
+    }
+
+    #[test]
+    fn info_is_clean() {
+        let (callback, capture) = Callback::capture(Default::default());
+        (callback.0)(&info("A"));
+        assert!(capture.is_clean());
+        capture.assert_clean();
+    }
+
+    #[test]
+    fn collect_mode_panics_only_on_assert() {
+        let (callback, capture) = Callback::capture(Default::default());
+        (callback.0)(&error("A"));
+
+        capture.check();
+        assert!(!capture.is_clean());
+        assert!(panics(|| capture.assert_clean()));
+    }
+
+    #[test]
+    fn panic_mode_raises_first_error_on_check() {
+        let config = CaptureConfig {
+            on_error: ErrorAction::Panic,
+            ..Default::default()
+        };
+        let (callback, capture) = Callback::capture(config);
+        (callback.0)(&warning("A"));
+        capture.check();
+
+        (callback.0)(&error("B"));
+        (callback.0)(&error("C"));
+        assert!(!capture.is_clean());
+        assert_eq!(capture.errors().len(), 2);
+
+        let raised = panic::catch_unwind(|| capture.check()).unwrap_err();
+        let raised = raised.downcast_ref::<String>().unwrap();
+        assert!(raised.contains("B message"), "{}", raised);
+        // Error is raised once.
+        capture.check();
+    }
+
+    #[test]
+    fn panic_mode_assert_clean_raises_pending_error() {
+        let config = CaptureConfig {
+            on_error: ErrorAction::Panic,
+            ..Default::default()
+        };
+        let (callback, capture) = Callback::capture(config);
+        (callback.0)(&error("A"));
+        capture.take_all();
+
+        assert!(capture.is_clean());
+        assert!(panics(|| capture.assert_clean()));
+        capture.assert_clean();
+    }
+}
@@ -0,0 +1,420 @@
+use crate::debug_report::{Callback, DebugMessage, MessageId};
+use ash::vk;
+use std::cmp::Reverse;
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+/// Count of distinct texts of unnamed messages, counted separately.
+/// Unnamed messages with other texts share one counter, so counters don't grow unbounded.
+pub const MAX_UNNAMED_TEXTS: usize = 256;
+
+#[derive(Debug, Clone)]
+pub struct FilterConfig {
+    /// If not empty, only messages with these IDs pass.
+    pub allow: Vec<MessageId>,
+    /// Messages with these IDs never pass.
+    pub deny: Vec<MessageId>,
+    /// Messages of lower severity never pass.
+    pub min_severity: vk::DebugUtilsMessageSeverityFlagsEXT,
+    /// Count of messages with the same ID, passed before the rest is suppressed.
+    /// Unnamed messages with zero ID number are compared by text, up to `MAX_UNNAMED_TEXTS`
+    /// texts. Unnamed messages with other texts are counted as one message.
+    /// `None` disables deduplication.
+    pub max_repeats: Option<u64>,
+}
+
+impl Default for FilterConfig {
+    fn default() -> Self {
+        Self {
+            allow: Vec::new(),
+            deny: Vec::new(),
+            min_severity: vk::DebugUtilsMessageSeverityFlagsEXT::VERBOSE,
+            max_repeats: None,
+        }
+    }
+}
+
+impl FilterConfig {
+    fn rejection(&self, msg: &DebugMessage) -> Option<Rejection> {
+        if msg.severity.as_raw() < self.min_severity.as_raw() {
+            return Some(Rejection::Severity);
+        }
+
+        let denied = self.deny.iter().any(|id| id.matches(msg));
+        let allowed = self.allow.is_empty() || self.allow.iter().any(|id| id.matches(msg));
+        match !denied && allowed {
+            true => None,
+            false => Some(Rejection::Id),
+        }
+    }
+}
+
+enum Rejection {
+    Id,
+    Severity,
+}
+
+/// Counters of messages with one ID.
+///
+/// Messages without ID name and number are counted by text. Messages, which text exceeds
+/// `MAX_UNNAMED_TEXTS` limit, are counted together with `text` set to `None`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MessageStats {
+    pub id_name: Option<String>,
+    pub id_number: i32,
+    /// Text of message without ID.
+    pub text: Option<String>,
+    pub received: u64,
+    pub passed: u64,
+    /// Suppressed by `allow` or `deny` list.
+    pub denied: u64,
+    /// Suppressed by `min_severity`.
+    pub below_severity: u64,
+    /// Suppressed by `max_repeats`.
+    pub repeated: u64,
+}
+
+impl MessageStats {
+    fn new(msg: &DebugMessage, key: &Key) -> Self {
+        let text = match key {
+            Key::Text(text) => Some(text.clone()),
+            Key::Id(..) | Key::OtherText => None,
+        };
+        Self {
+            id_name: msg.message_id_name.clone(),
+            id_number: msg.message_id_number,
+            text,
+            received: 0,
+            passed: 0,
+            denied: 0,
+            below_severity: 0,
+            repeated: 0,
+        }
+    }
+
+    /// Count of suppressed messages for any reason.
+    pub fn suppressed(&self) -> u64 {
+        self.denied + self.below_severity + self.repeated
+    }
+}
+
+#[derive(PartialEq, Eq, Hash)]
+enum Key {
+    Id(Option<String>, i32),
+    /// Unnamed messages with zero number differ only by text.
+    Text(String),
+    /// Unnamed messages with zero number, which texts exceed `MAX_UNNAMED_TEXTS`.
+    OtherText,
+}
+
+impl Key {
+    fn of(msg: &DebugMessage) -> Self {
+        match (&msg.message_id_name, msg.message_id_number) {
+            (None, 0) => Key::Text(msg.message.clone()),
+            (name, number) => Key::Id(name.clone(), number),
+        }
+    }
+}
+
+#[derive(Default)]
+struct State {
+    config: FilterConfig,
+    stats: HashMap<Key, MessageStats>,
+    /// Count of `Key::Text` keys in `stats`.
+    texts: usize,
+}
+
+impl State {
+    fn key(&mut self, msg: &DebugMessage) -> Key {
+        let key = Key::of(msg);
+        if matches!(key, Key::Text(_)) && !self.stats.contains_key(&key) {
+            if self.texts >= MAX_UNNAMED_TEXTS {
+                return Key::OtherText;
+            }
+            self.texts += 1;
+        }
+        key
+    }
+}
+
+/// Filter of debug messages by ID, severity and repeat count.
+///
+/// Clones share configuration and counters, so filter may be reconfigured at runtime
+/// while used by messenger.
+#[derive(Clone, Default)]
+pub struct MessageFilter {
+    state: Arc<Mutex<State>>,
+}
+
+impl MessageFilter {
+    pub fn new(config: FilterConfig) -> Self {
+        let state = State {
+            config,
+            stats: HashMap::new(),
+            texts: 0,
+        };
+        Self {
+            state: Arc::new(Mutex::new(state)),
+        }
+    }
+
+    pub fn config(&self) -> FilterConfig {
+        self.lock().config.clone()
+    }
+
+    pub fn set_config(&self, config: FilterConfig) {
+        self.lock().config = config;
+    }
+
+    pub fn set_min_severity(&self, severity: vk::DebugUtilsMessageSeverityFlagsEXT) {
+        self.lock().config.min_severity = severity;
+    }
+
+    pub fn set_max_repeats(&self, max_repeats: Option<u64>) {
+        self.lock().config.max_repeats = max_repeats;
+    }
+
+    /// Counts `msg` and checks if it should be passed.
+    pub fn pass(&self, msg: &DebugMessage) -> bool {
+        let mut state = self.lock();
+        let rejection = state.config.rejection(msg);
+        let max_repeats = state.config.max_repeats;
+
+        let key = state.key(msg);
+        let stats = match state.stats.entry(key) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => {
+                let stats = MessageStats::new(msg, entry.key());
+                entry.insert(stats)
+            }
+        };
+        stats.received += 1;
+
+        let repeated = matches!(max_repeats, Some(max) if stats.passed >= max);
+        match rejection {
+            Some(Rejection::Id) => stats.denied += 1,
+            Some(Rejection::Severity) => stats.below_severity += 1,
+            None if repeated => stats.repeated += 1,
+            None => {
+                stats.passed += 1;
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Counters of messages with at least one suppressed, sorted by suppressed count.
+    pub fn suppressed(&self) -> Vec<MessageStats> {
+        let mut stats: Vec<_> = self
+            .lock()
+            .stats
+            .values()
+            .filter(|s| s.suppressed() > 0)
+            .cloned()
+            .collect();
+        stats.sort_by_key(|s| Reverse(s.suppressed()));
+        stats
+    }
+
+    pub fn reset_counters(&self) {
+        let mut state = self.lock();
+        state.stats.clear();
+        state.texts = 0;
+    }
+
+    /// Callback, passing filtered messages to `callback`.
+    /// Suppressed message counts are logged when it is dropped together with messenger.
+    pub fn wrap(&self, callback: Callback) -> Callback {
+        let filtered = Filtered {
+            filter: self.clone(),
+            callback,
+        };
+        Callback(Box::new(move |msg| filtered.call(msg)))
+    }
+
+    fn lock(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+struct Filtered {
+    filter: MessageFilter,
+    callback: Callback,
+}
+
+impl Filtered {
+    fn call(&self, msg: &DebugMessage) {
+        if self.filter.pass(msg) {
+            self.callback.0(msg)
+        }
+    }
+}
+
+impl Drop for Filtered {
+    fn drop(&mut self) {
+        for stats in self.filter.suppressed() {
+            let name = match (&stats.id_name, &stats.text) {
+                (Some(name), _) => name.as_str(),
+                (None, Some(text)) => text.as_str(),
+                (None, None) => "<unnamed>",
+            };
+            log::info!(
+                "Vulkan message {} ({:#x}): received {}, denied {}, below severity {}, repeated {}",
+                name,
+                stats.id_number,
+                stats.received,
+                stats.denied,
+                stats.below_severity,
+                stats.repeated
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::debug_report::MessageLevel;
+
+    fn message(name: Option<&str>, number: i32, text: &str) -> DebugMessage {
+        DebugMessage {
+            level: MessageLevel::Warning,
+            severity: vk::DebugUtilsMessageSeverityFlagsEXT::WARNING,
+            types: vk::DebugUtilsMessageTypeFlagsEXT::VALIDATION,
+            message_id_name: name.map(String::from),
+            message_id_number: number,
+            message: text.into(),
+            queue_labels: Vec::new(),
+            cmd_buf_labels: Vec::new(),
+            objects: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn repeats_are_suppressed_after_max() {
+        let filter = MessageFilter::new(FilterConfig {
+            max_repeats: Some(2),
+            ..Default::default()
+        });
+        let msg = message(Some("VUID-1"), 1, "first");
+
+        let passed: Vec<_> = (0..4).map(|_| filter.pass(&msg)).collect();
+        assert_eq!(passed, [true, true, false, false]);
+
+        let stats = filter.suppressed();
+        assert_eq!(stats.len(), 1);
+        assert_eq!(stats[0].received, 4);
+        assert_eq!(stats[0].passed, 2);
+        assert_eq!(stats[0].repeated, 2);
+        assert_eq!(stats[0].suppressed(), 2);
+    }
+
+    #[test]
+    fn unnamed_messages_are_deduplicated_by_text() {
+        let filter = MessageFilter::new(FilterConfig {
+            max_repeats: Some(1),
+            ..Default::default()
+        });
+
+        assert!(filter.pass(&message(None, 0, "first")));
+        assert!(filter.pass(&message(None, 0, "second")));
+        assert!(!filter.pass(&message(None, 0, "first")));
+
+        let stats = filter.suppressed();
+        assert_eq!(stats.len(), 1);
+        assert_eq!(stats[0].text.as_deref(), Some("first"));
+    }
+
+    #[test]
+    fn unnamed_texts_over_limit_share_counter() {
+        let filter = MessageFilter::new(FilterConfig {
+            max_repeats: Some(0),
+            ..Default::default()
+        });
+
+        for i in 0..MAX_UNNAMED_TEXTS + 10 {
+            assert!(!filter.pass(&message(None, 0, &format!("text {}", i))));
+        }
+        assert!(!filter.pass(&message(None, 0, "text 0")));
+
+        let stats = filter.suppressed();
+        assert_eq!(stats.len(), MAX_UNNAMED_TEXTS + 1);
+        assert_eq!(filter.lock().texts, MAX_UNNAMED_TEXTS);
+        let other = stats.iter().find(|s| s.text.is_none()).unwrap();
+        assert_eq!(other.received, 10);
+        let first = stats.iter().find(|s| s.text.as_deref() == Some("text 0"));
+        assert_eq!(first.unwrap().received, 2);
+
+        filter.reset_counters();
+        assert!(!filter.pass(&message(None, 0, "new text")));
+        assert_eq!(filter.suppressed()[0].text.as_deref(), Some("new text"));
+    }
+
+    #[test]
+    fn named_messages_are_deduplicated_by_id() {
+        let filter = MessageFilter::new(FilterConfig {
+            max_repeats: Some(1),
+            ..Default::default()
+        });
+
+        assert!(filter.pass(&message(Some("VUID-1"), 0, "first")));
+        assert!(!filter.pass(&message(Some("VUID-1"), 0, "second")));
+        assert_eq!(filter.suppressed()[0].text, None);
+    }
+
+    #[test]
+    fn rejections_are_counted_separately() {
+        let filter = MessageFilter::new(FilterConfig {
+            deny: vec!["VUID-1".into()],
+            min_severity: vk::DebugUtilsMessageSeverityFlagsEXT::ERROR,
+            max_repeats: Some(0),
+            ..Default::default()
+        });
+
+        let msg = message(Some("VUID-1"), 1, "denied");
+        assert!(!filter.pass(&msg));
+        filter.set_min_severity(vk::DebugUtilsMessageSeverityFlagsEXT::VERBOSE);
+        assert!(!filter.pass(&msg));
+        filter.set_config(FilterConfig {
+            max_repeats: Some(0),
+            ..Default::default()
+        });
+        assert!(!filter.pass(&msg));
+
+        let stats = &filter.suppressed()[0];
+        assert_eq!(stats.below_severity, 1);
+        assert_eq!(stats.denied, 1);
+        assert_eq!(stats.repeated, 1);
+        assert_eq!(stats.received, 3);
+    }
+
+    #[test]
+    fn allow_list_rejections_are_denied() {
+        let filter = MessageFilter::new(FilterConfig {
+            allow: vec![2.into()],
+            max_repeats: Some(1),
+            ..Default::default()
+        });
+
+        assert!(!filter.pass(&message(None, 1, "not allowed")));
+        assert!(filter.pass(&message(None, 2, "allowed")));
+        assert!(!filter.pass(&message(None, 2, "allowed")));
+
+        let stats = filter.suppressed();
+        assert_eq!(stats.len(), 2);
+        assert!(stats.iter().any(|s| s.id_number == 1 && s.denied == 1));
+        assert!(stats.iter().any(|s| s.id_number == 2 && s.repeated == 1));
+    }
+
+    #[test]
+    fn reset_clears_counters() {
+        let filter = MessageFilter::new(FilterConfig {
+            max_repeats: Some(0),
+            ..Default::default()
+        });
+        assert!(!filter.pass(&message(None, 3, "text")));
+        filter.reset_counters();
+        assert!(filter.suppressed().is_empty());
+    }
+}
@@ -1,54 +1,133 @@
 use crate::instance::Instance;
+use crate::registry::Owner;
 use crate::{Handle, RawHandle};
 use ash::extensions::ext;
+use ash::prelude::VkResult;
 use ash::vk;
 use std::ffi::{c_void, CStr};
 use std::fmt;
-use std::marker::PhantomData;
+use std::os::raw::c_char;
+use std::panic::{self, AssertUnwindSafe};
+use std::process;
+use std::slice;
 
+/// Debug messenger with data, referenced by its callback.
+///
+/// Messenger is destroyed on drop, before `user_data` and `instance` are released. So they
+/// outlive it even if `DebugMessanger` is unwrapped by `Handle::try_unwrap`.
 pub struct RawDebugMessenger<UserData> {
     handle: vk::DebugUtilsMessengerEXT,
     debug_utils: ext::DebugUtils,
-    _user_data: PhantomData<UserData>,
+    user_data: Option<Box<UserData>>,
+    _instance: Instance,
 }
 
 impl<UserData> RawDebugMessenger<UserData> {
-    pub fn new(handle: vk::DebugUtilsMessengerEXT, debug_report: ext::DebugUtils) -> Self {
+    /// # Safety
+    /// * `handle` must be valid messenger, created by `debug_utils` of `instance`.
+    /// * callback of messenger may reference only `user_data` and data, outliving the messenger.
+    /// * `handle` must not be destroyed elsewhere.
+    pub unsafe fn new(
+        handle: vk::DebugUtilsMessengerEXT,
+        debug_utils: ext::DebugUtils,
+        user_data: Option<Box<UserData>>,
+        instance: Instance,
+    ) -> Self {
         Self {
             handle,
-            debug_utils: debug_report,
-            _user_data: Default::default(),
+            debug_utils,
+            user_data,
+            _instance: instance,
         }
     }
 
     pub fn handle(&self) -> &vk::DebugUtilsMessengerEXT {
         &self.handle
     }
+
+    pub fn user_data(&self) -> Option<&UserData> {
+        self.user_data.as_deref()
+    }
+}
+
+impl<UserData> Drop for RawDebugMessenger<UserData> {
+    fn drop(&mut self) {
+        unsafe {
+            self.debug_utils
+                .destroy_debug_utils_messenger(self.handle, None)
+        }
+    }
 }
 
-pub struct Deps<UserData> {
+pub struct Deps {
     pub instance: Instance,
-    pub user_data: Option<Box<UserData>>,
 }
 
 impl<UserData> RawHandle for RawDebugMessenger<UserData> {
-    type Dependencies = Deps<UserData>;
+    type Dependencies = Deps;
 
     fn name() -> &'static str {
         "debug report"
     }
 
-    fn destroy(&self, _: &Self::Dependencies) {
+    fn owner(deps: &Self::Dependencies) -> Option<Owner> {
+        Some(Owner::of_instance(&deps.instance))
+    }
+
+    fn object_type() -> vk::ObjectType {
+        vk::ObjectType::DEBUG_UTILS_MESSENGER_EXT
+    }
+
+    fn raw_handles(&self) -> Vec<u64> {
+        vec![vk::Handle::as_raw(self.handle)]
+    }
+
+    /// Messenger is destroyed by drop of `RawDebugMessenger`.
+    fn destroy(&self, _: &Self::Dependencies) {}
+}
+
+pub type DebugMessanger<UserData> = Handle<RawDebugMessenger<UserData>, Deps>;
+
+/// Debug messenger, owning its `Callback`.
+pub type DebugMessenger = DebugMessanger<Callback>;
+
+impl DebugMessenger {
+    /// Creates messenger, passing messages of `severity` and `types` to `callback`.
+    /// `callback` is owned by `RawDebugMessenger` and outlives the messenger.
+    /// Fails with `vk::Result::ERROR_EXTENSION_NOT_PRESENT` if `VK_EXT_debug_utils` is not enabled
+    /// on `instance`.
+    pub fn create(
+        instance: &Instance,
+        severity: vk::DebugUtilsMessageSeverityFlagsEXT,
+        types: vk::DebugUtilsMessageTypeFlagsEXT,
+        callback: Callback,
+    ) -> VkResult<Self> {
+        let mut user_data = Box::new(callback);
+        let callback_ptr: *mut Callback = user_data.as_mut();
+
+        let ci = vk::DebugUtilsMessengerCreateInfoEXT::builder()
+            .message_severity(severity)
+            .message_type(types)
+            .user_data(callback_ptr.cast())
+            .pfn_user_callback(Some(debug_report_with_default_callback));
+
+        let debug_utils = match &instance.dependencies().debug_utils {
+            Some(debug_utils) => debug_utils.clone(),
+            None => return Err(vk::Result::ERROR_EXTENSION_NOT_PRESENT),
+        };
+
         unsafe {
-            self.debug_utils
-                .destroy_debug_utils_messenger(self.handle, None)
+            let raw = debug_utils.create_debug_utils_messenger(&ci, None)?;
+            let raw = RawDebugMessenger::new(raw, debug_utils, Some(user_data), instance.clone());
+            let deps = Deps {
+                instance: instance.clone(),
+            };
+            Ok(Self::new(raw, deps))
         }
     }
 }
 
-pub type DebugMessanger<UserData> = Handle<RawDebugMessenger<UserData>, Deps<UserData>>;
-
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum MessageLevel {
     Information,
     Warning,
@@ -57,6 +136,20 @@ pub enum MessageLevel {
     Debug,
 }
 
+impl MessageLevel {
+    /// Performance messages, which are not errors, have `Perfomance` level.
+    pub fn new(
+        severity: vk::DebugUtilsMessageSeverityFlagsEXT,
+        types: vk::DebugUtilsMessageTypeFlagsEXT,
+    ) -> Self {
+        let level = Self::from(severity);
+        if level != Self::Error && types.contains(vk::DebugUtilsMessageTypeFlagsEXT::PERFORMANCE) {
+            return Self::Perfomance;
+        }
+        level
+    }
+}
+
 impl From<vk::DebugUtilsMessageSeverityFlagsEXT> for MessageLevel {
     fn from(flags: vk::DebugUtilsMessageSeverityFlagsEXT) -> Self {
         if flags.contains(vk::DebugUtilsMessageSeverityFlagsEXT::ERROR) {
@@ -75,7 +168,6 @@ impl From<vk::DebugUtilsMessageSeverityFlagsEXT> for MessageLevel {
             return Self::Information;
         }
 
-
         Self::Error
     }
 }
@@ -104,17 +196,154 @@ impl From<MessageLevel> for log::Level {
     }
 }
 
-pub struct Callback(pub Box<dyn Fn(String, MessageLevel) + Send + Sync + 'static>);
+/// Owned `vk::DebugUtilsLabelEXT`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DebugLabel {
+    pub name: String,
+    pub color: [f32; 4],
+}
+
+/// Owned `vk::DebugUtilsObjectNameInfoEXT`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DebugObject {
+    pub object_type: vk::ObjectType,
+    pub handle: u64,
+    pub name: Option<String>,
+}
+
+/// Owned `vk::DebugUtilsMessengerCallbackDataEXT` with message severity and type.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DebugMessage {
+    pub level: MessageLevel,
+    pub severity: vk::DebugUtilsMessageSeverityFlagsEXT,
+    pub types: vk::DebugUtilsMessageTypeFlagsEXT,
+    pub message_id_name: Option<String>,
+    pub message_id_number: i32,
+    pub message: String,
+    pub queue_labels: Vec<DebugLabel>,
+    pub cmd_buf_labels: Vec<DebugLabel>,
+    pub objects: Vec<DebugObject>,
+}
+
+impl DebugMessage {
+    /// # Safety
+    /// * `data` must be valid callback data, passed to messenger callback by Vulkan.
+    pub unsafe fn from_raw(
+        severity: vk::DebugUtilsMessageSeverityFlagsEXT,
+        types: vk::DebugUtilsMessageTypeFlagsEXT,
+        data: &vk::DebugUtilsMessengerCallbackDataEXT,
+    ) -> Self {
+        let labels = |ptr, count| {
+            raw_slice(ptr, count)
+                .iter()
+                .map(|l: &vk::DebugUtilsLabelEXT| DebugLabel {
+                    name: owned_str(l.p_label_name).unwrap_or_default(),
+                    color: l.color,
+                })
+                .collect()
+        };
+
+        let objects = raw_slice(data.p_objects, data.object_count)
+            .iter()
+            .map(|o| DebugObject {
+                object_type: o.object_type,
+                handle: o.object_handle,
+                name: owned_str(o.p_object_name),
+            })
+            .collect();
+
+        Self {
+            level: MessageLevel::new(severity, types),
+            severity,
+            types,
+            message_id_name: owned_str(data.p_message_id_name),
+            message_id_number: data.message_id_number,
+            message: owned_str(data.p_message).unwrap_or_default(),
+            queue_labels: labels(data.p_queue_labels, data.queue_label_count),
+            cmd_buf_labels: labels(data.p_cmd_buf_labels, data.cmd_buf_label_count),
+            objects,
+        }
+    }
+}
+
+impl fmt::Display for DebugMessage {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.message_id_name {
+            Some(id) => write!(
+                f,
+                "{} ({:#x}): {}",
+                id, self.message_id_number, self.message
+            ),
+            None => write!(f, "{}", self.message),
+        }
+    }
+}
+
+unsafe fn owned_str(ptr: *const c_char) -> Option<String> {
+    match ptr.is_null() {
+        true => None,
+        false => Some(CStr::from_ptr(ptr).to_string_lossy().into_owned()),
+    }
+}
+
+unsafe fn raw_slice<'a, T>(ptr: *const T, count: u32) -> &'a [T] {
+    match ptr.is_null() {
+        true => &[],
+        false => slice::from_raw_parts(ptr, count as usize),
+    }
+}
+
+pub struct Callback(pub Box<dyn Fn(&DebugMessage) + Send + Sync + 'static>);
 
 impl Callback {
     pub fn cout_reports() -> Self {
-        let callback = |msg, level| println!("Vulkan callback report [{}]: {}", level, msg);
+        let callback =
+            |msg: &DebugMessage| println!("Vulkan callback report [{}]: {}", msg.level, msg);
         Self(Box::new(callback))
     }
 
     pub fn log_reports() -> Self {
-        let callback = |msg, level: MessageLevel| {
-            log::log!(level.into(), "Vulkan callback report [{}]: {}", level, msg)
+        let callback = |msg: &DebugMessage| {
+            log::log!(
+                msg.level.into(),
+                "Vulkan callback report [{}]: {}",
+                msg.level,
+                msg
+            )
+        };
+        Self(Box::new(callback))
+    }
+}
+
+#[cfg(feature = "tracing")]
+impl Callback {
+    /// Forwards messages as `tracing` events with message ID, type, labels and objects fields.
+    pub fn tracing_reports() -> Self {
+        let callback = |msg: &DebugMessage| {
+            macro_rules! event {
+                ($level:expr) => {
+                    tracing::event!(
+                        target: "vk_raii::debug_report",
+                        $level,
+                        message_id_name = msg.message_id_name.as_deref().unwrap_or(""),
+                        message_id_number = msg.message_id_number,
+                        message_types = ?msg.types,
+                        queue_labels = ?msg.queue_labels,
+                        cmd_buf_labels = ?msg.cmd_buf_labels,
+                        objects = ?msg.objects,
+                        "{}",
+                        msg.message
+                    )
+                };
+            }
+
+            match msg.level {
+                MessageLevel::Information => event!(tracing::Level::INFO),
+                MessageLevel::Warning => event!(tracing::Level::WARN),
+                MessageLevel::Perfomance => event!(tracing::Level::WARN),
+                MessageLevel::Error => event!(tracing::Level::ERROR),
+                MessageLevel::Debug => event!(tracing::Level::DEBUG),
+            }
         };
         Self(Box::new(callback))
     }
@@ -126,22 +355,58 @@ impl Drop for Callback {
     }
 }
 
+/// Identifier of validation message.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum MessageId {
+    Name(String),
+    Number(i32),
+}
+
+impl MessageId {
+    pub fn matches(&self, msg: &DebugMessage) -> bool {
+        match self {
+            MessageId::Name(name) => msg.message_id_name.as_ref() == Some(name),
+            MessageId::Number(number) => msg.message_id_number == *number,
+        }
+    }
+}
+
+impl From<&str> for MessageId {
+    fn from(name: &str) -> Self {
+        Self::Name(name.into())
+    }
+}
+
+impl From<i32> for MessageId {
+    fn from(number: i32) -> Self {
+        Self::Number(number)
+    }
+}
+
 /// # Safety
 /// * `p_user_data` must be valid pointer to `Callback` struct;
 /// * To destroy callback correctly, save it in `Dependencies` of `DebugReport`
+///
+/// Panic in callback can't unwind through Vulkan, so process is aborted after panic hook.
 pub unsafe extern "system" fn debug_report_with_default_callback(
     msg_severity: vk::DebugUtilsMessageSeverityFlagsEXT,
-    _msg_type: vk::DebugUtilsMessageTypeFlagsEXT,
+    msg_type: vk::DebugUtilsMessageTypeFlagsEXT,
     callback_data: *const vk::DebugUtilsMessengerCallbackDataEXT,
     p_user_data: *mut c_void,
 ) -> vk::Bool32 {
     let callback: *mut Callback = p_user_data.cast();
     let callback_ref = callback.as_ref();
-    let msg = CStr::from_ptr((*callback_data).p_message);
-    let str = msg.to_string_lossy();
-    let level = msg_severity.into();
+    let data = match callback_data.as_ref() {
+        Some(data) => data,
+        None => return vk::FALSE,
+    };
+    let msg = DebugMessage::from_raw(msg_severity, msg_type, data);
     match callback_ref {
-        Some(cb) => cb.0(format!("{}", str), level),
+        Some(cb) => {
+            if panic::catch_unwind(AssertUnwindSafe(|| cb.0(&msg))).is_err() {
+                process::abort()
+            }
+        }
         None => eprintln!("Can't dereference vk debug report callback pointer"),
     }
 
@@ -0,0 +1,282 @@
+use crate::device::Device;
+use crate::fence::Fence;
+use crate::semaphore::TimelineSemaphore;
+use crate::{RawHandle, UniqueHandle};
+use ash::prelude::VkResult;
+use std::collections::VecDeque;
+use std::mem;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+/// GPU progress point, after which handles of a frame may be destroyed.
+pub enum Marker {
+    Fence(Fence),
+    /// Reached when counter of semaphore is greater or equal to value.
+    Timeline(TimelineSemaphore, u64),
+}
+
+impl Marker {
+    pub fn is_reached(&self) -> VkResult<bool> {
+        match self {
+            Marker::Fence(fence) => fence.is_signaled(),
+            Marker::Timeline(semaphore, value) => Ok(semaphore.counter_value()? >= *value),
+        }
+    }
+}
+
+/// Dropped handle, destroyed when dropped itself.
+type Retired = Box<dyn Send>;
+
+struct Batch<M> {
+    marker: M,
+    items: Vec<Retired>,
+}
+
+struct State<M> {
+    pending: Vec<Retired>,
+    batches: VecDeque<Batch<M>>,
+}
+
+impl<M> Default for State<M> {
+    fn default() -> Self {
+        Self {
+            pending: Vec::new(),
+            batches: VecDeque::new(),
+        }
+    }
+}
+
+impl<M> State<M> {
+    /// Associates pending items with `marker`. Returns `marker` back, if there are none.
+    fn end_frame(&mut self, marker: M) -> Option<M> {
+        if self.pending.is_empty() {
+            return Some(marker);
+        }
+        let items = mem::take(&mut self.pending);
+        self.batches.push_back(Batch { marker, items });
+        None
+    }
+
+    /// Removes batches with reached markers. Nothing is removed on error.
+    fn take_reached(
+        &mut self,
+        mut is_reached: impl FnMut(&M) -> VkResult<bool>,
+    ) -> VkResult<Vec<Batch<M>>> {
+        let reached = self
+            .batches
+            .iter()
+            .map(|b| is_reached(&b.marker))
+            .collect::<VkResult<Vec<bool>>>()?;
+
+        let batches = mem::take(&mut self.batches);
+        let (reached, kept): (Vec<_>, Vec<_>) = batches
+            .into_iter()
+            .zip(reached)
+            .partition(|(_, reached)| *reached);
+        self.batches = kept.into_iter().map(|(b, _)| b).collect();
+        Ok(reached.into_iter().map(|(b, _)| b).collect())
+    }
+
+    fn len(&self) -> usize {
+        let in_batches: usize = self.batches.iter().map(|b| b.items.len()).sum();
+        self.pending.len() + in_batches
+    }
+
+    fn is_empty(&self) -> bool {
+        self.pending.is_empty() && self.batches.is_empty()
+    }
+}
+
+#[derive(Default)]
+pub(crate) struct Shared {
+    state: Mutex<State<Marker>>,
+}
+
+impl Shared {
+    fn lock(&self) -> MutexGuard<'_, State<Marker>> {
+        self.state.lock().expect("Deletion queue mutex poisoned")
+    }
+}
+
+/// Queue of dropped handles, waiting for GPU to stop using them.
+///
+/// Handles, created by `Handle::new_deferred` or moved here by `Handle::try_defer`, are not
+/// destroyed on drop. Instead they are collected until `end_frame` associates them with
+/// a `Marker`, and destroyed by `collect` after the marker is reached.
+///
+/// Dropping `DeletionQueue` waits for device idle and destroys all collected handles.
+/// Handles, dropped after that, are destroyed immediately.
+pub struct DeletionQueue {
+    device: Device,
+    shared: Arc<Shared>,
+}
+
+impl DeletionQueue {
+    pub fn new(device: &Device) -> Self {
+        Self {
+            device: device.clone(),
+            shared: Default::default(),
+        }
+    }
+
+    pub fn device(&self) -> &Device {
+        &self.device
+    }
+
+    pub(crate) fn shared(&self) -> &Arc<Shared> {
+        &self.shared
+    }
+
+    /// Associates handles, dropped since previous call, with `marker`.
+    pub fn end_frame(&self, marker: Marker) {
+        let unused = self.shared.lock().end_frame(marker);
+        // Marker may be deferred handle itself, so it is dropped outside of lock.
+        drop(unused);
+    }
+
+    /// Destroys handles of frames with reached markers. Returns count of destroyed handles.
+    pub fn collect(&self) -> VkResult<usize> {
+        let retired = self.shared.lock().take_reached(Marker::is_reached)?;
+
+        // Destroyed outside of lock: dependencies may be deferred handles too.
+        let count = retired.iter().map(|b| b.items.len()).sum();
+        drop(retired);
+        Ok(count)
+    }
+
+    /// Waits for device idle and destroys all collected handles, including ones without marker.
+    pub fn flush(&self) -> VkResult<()> {
+        self.device.wait_idle()?;
+        self.destroy_all();
+        Ok(())
+    }
+
+    /// Count of handles, waiting for destruction.
+    pub fn len(&self) -> usize {
+        self.shared.lock().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn destroy_all(&self) {
+        loop {
+            let state = mem::take(&mut *self.shared.lock());
+            if state.is_empty() {
+                return;
+            }
+            drop(state);
+        }
+    }
+}
+
+impl Drop for DeletionQueue {
+    fn drop(&mut self) {
+        if let Err(e) = self.device.wait_idle() {
+            log::error!("Can't wait device idle before deletion queue flush: {}", e);
+        }
+        self.destroy_all();
+    }
+}
+
+pub(crate) fn retire<T, D>(unique: UniqueHandle<T, D>, shared: &Shared)
+where
+    T: RawHandle<Dependencies = D> + Send + 'static,
+    D: Send + 'static,
+{
+    shared.lock().pending.push(Box::new(unique));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ash::vk;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    struct Item(Arc<AtomicUsize>);
+
+    impl Drop for Item {
+        fn drop(&mut self) {
+            self.0.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    fn retire(state: &mut State<u64>, dropped: &Arc<AtomicUsize>, count: usize) {
+        for _ in 0..count {
+            state.pending.push(Box::new(Item(dropped.clone())));
+        }
+    }
+
+    #[test]
+    fn empty_frame_returns_marker() {
+        let mut state = State::default();
+        assert_eq!(state.end_frame(1), Some(1));
+        assert!(state.batches.is_empty());
+    }
+
+    #[test]
+    fn frame_takes_pending_items() {
+        let dropped = Arc::new(AtomicUsize::new(0));
+        let mut state = State::default();
+        retire(&mut state, &dropped, 2);
+        assert_eq!(state.end_frame(1), None);
+        retire(&mut state, &dropped, 1);
+
+        assert_eq!(state.len(), 3);
+        assert_eq!(state.pending.len(), 1);
+        assert_eq!(state.batches.len(), 1);
+        assert_eq!(state.batches[0].items.len(), 2);
+    }
+
+    #[test]
+    fn only_reached_batches_are_taken() {
+        let dropped = Arc::new(AtomicUsize::new(0));
+        let mut state = State::default();
+        for frame in 1..=3 {
+            retire(&mut state, &dropped, frame as usize);
+            state.end_frame(frame);
+        }
+
+        // Markers may be reached out of order.
+        let taken = state.take_reached(|&m| Ok(m != 2)).unwrap();
+        let markers: Vec<_> = taken.iter().map(|b| b.marker).collect();
+        assert_eq!(markers, [1, 3]);
+        assert_eq!(dropped.load(Ordering::SeqCst), 0);
+
+        drop(taken);
+        assert_eq!(dropped.load(Ordering::SeqCst), 4);
+        assert_eq!(state.len(), 2);
+        assert_eq!(state.batches[0].marker, 2);
+    }
+
+    #[test]
+    fn nothing_is_taken_on_error() {
+        let dropped = Arc::new(AtomicUsize::new(0));
+        let mut state = State::default();
+        for frame in 1..=2 {
+            retire(&mut state, &dropped, 1);
+            state.end_frame(frame);
+        }
+
+        let result = state.take_reached(|&m| match m {
+            1 => Ok(true),
+            _ => Err(vk::Result::ERROR_DEVICE_LOST),
+        });
+        assert_eq!(result.err(), Some(vk::Result::ERROR_DEVICE_LOST));
+        assert_eq!(state.batches.len(), 2);
+        assert_eq!(dropped.load(Ordering::SeqCst), 0);
+    }
+
+    #[test]
+    fn taken_state_is_empty() {
+        let dropped = Arc::new(AtomicUsize::new(0));
+        let mut state = State::default();
+        retire(&mut state, &dropped, 1);
+        state.end_frame(1);
+        retire(&mut state, &dropped, 1);
+
+        drop(mem::take(&mut state));
+        assert!(state.is_empty());
+        assert_eq!(dropped.load(Ordering::SeqCst), 2);
+    }
+}
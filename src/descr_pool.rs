@@ -1,10 +1,57 @@
 use crate::device::Device;
 use crate::{Handle, RawHandle};
+use ash::prelude::VkResult;
 use ash::version::DeviceV1_0;
 use ash::vk;
+use std::hash::Hash;
+use std::sync::{Mutex, MutexGuard};
+
+#[derive(Debug, Clone, Default)]
+pub struct DescriptorPoolDesc {
+    pub flags: vk::DescriptorPoolCreateFlags,
+    pub max_sets: u32,
+    pub pool_sizes: Vec<vk::DescriptorPoolSize>,
+}
+
+impl DescriptorPoolDesc {
+    pub fn create_info(&self) -> vk::DescriptorPoolCreateInfoBuilder<'_> {
+        vk::DescriptorPoolCreateInfo::builder()
+            .flags(self.flags)
+            .max_sets(self.max_sets)
+            .pool_sizes(&self.pool_sizes)
+    }
+
+    fn key(&self) -> impl Eq + Hash {
+        let sizes: Vec<_> = self
+            .pool_sizes
+            .iter()
+            .map(|size| (size.ty, size.descriptor_count))
+            .collect();
+        (self.flags, self.max_sets, sizes)
+    }
+}
+
+impl_eq_hash_by_key!(DescriptorPoolDesc);
 
 pub struct Deps {
     pub device: Device,
+    pub desc: DescriptorPoolDesc,
+    /// Synchronizes allocation and freeing of descriptor sets between clones.
+    lock: Mutex<()>,
+}
+
+impl Deps {
+    pub fn new(device: Device, desc: DescriptorPoolDesc) -> Self {
+        Self {
+            device,
+            desc,
+            lock: Mutex::new(()),
+        }
+    }
+
+    pub(crate) fn lock(&self) -> MutexGuard<'_, ()> {
+        self.lock.lock().unwrap_or_else(|e| e.into_inner())
+    }
 }
 
 impl RawHandle for vk::DescriptorPool {
@@ -14,9 +61,35 @@ impl RawHandle for vk::DescriptorPool {
         "descriptor pool"
     }
 
+    fn device(deps: &Self::Dependencies) -> Option<&Device> {
+        Some(&deps.device)
+    }
+
+    fn object_type() -> vk::ObjectType {
+        vk::ObjectType::DESCRIPTOR_POOL
+    }
+
+    fn raw_handles(&self) -> Vec<u64> {
+        vec![vk::Handle::as_raw(*self)]
+    }
+
     fn destroy(&self, dependencies: &Self::Dependencies) {
         unsafe { dependencies.device.destroy_descriptor_pool(*self, None) }
     }
 }
 
 pub type DescriptorPool = Handle<vk::DescriptorPool, Deps>;
+
+impl DescriptorPool {
+    pub fn create(device: &Device, desc: &DescriptorPoolDesc) -> VkResult<Self> {
+        unsafe {
+            let raw = device.create_descriptor_pool(&desc.create_info(), None)?;
+            let deps = Deps::new(device.clone(), desc.clone());
+            Ok(Self::new(raw, deps))
+        }
+    }
+
+    pub fn desc(&self) -> &DescriptorPoolDesc {
+        &self.dependencies().desc
+    }
+}
@@ -1,6 +1,8 @@
 use crate::descr_pool::DescriptorPool;
+use crate::device::Device;
 use crate::ds_layout::DescriptorSetLayout;
 use crate::{Handle, RawHandle};
+use ash::prelude::VkResult;
 use ash::version::DeviceV1_0;
 use ash::vk;
 
@@ -17,16 +19,36 @@ impl RawHandle for vk::DescriptorSet {
         "descriptor set"
     }
 
+    fn device(deps: &Self::Dependencies) -> Option<&Device> {
+        Some(&deps.pool.dependencies().device)
+    }
+
+    fn object_type() -> vk::ObjectType {
+        vk::ObjectType::DESCRIPTOR_SET
+    }
+
+    fn raw_handles(&self) -> Vec<u64> {
+        vec![vk::Handle::as_raw(*self)]
+    }
+
     fn destroy(&self, deps: &Self::Dependencies) {
         if deps.can_free {
-            let device = &deps.pool.dependencies().device;
-            let _ = unsafe { device.free_descriptor_sets(*deps.pool, &[*self]) };
+            let pool_deps = deps.pool.dependencies();
+            let _lock = pool_deps.lock();
+            let _ = unsafe { pool_deps.device.free_descriptor_sets(*deps.pool, &[*self]) };
         }
     }
 }
 
 pub type DescriptorSet = Handle<vk::DescriptorSet, Deps>;
 
+impl DescriptorSet {
+    pub fn allocate(pool: &DescriptorPool, ds_layout: &DescriptorSetLayout) -> VkResult<Self> {
+        let (mut raw, deps) = allocate(pool, std::slice::from_ref(ds_layout))?;
+        unsafe { Ok(Self::new(raw.remove(0), deps)) }
+    }
+}
+
 impl RawHandle for Vec<vk::DescriptorSet> {
     type Dependencies = Deps;
 
@@ -34,14 +56,63 @@ impl RawHandle for Vec<vk::DescriptorSet> {
         "descroptor sets"
     }
 
+    fn device(deps: &Self::Dependencies) -> Option<&Device> {
+        Some(&deps.pool.dependencies().device)
+    }
+
+    fn object_type() -> vk::ObjectType {
+        vk::ObjectType::DESCRIPTOR_SET
+    }
+
+    fn raw_handles(&self) -> Vec<u64> {
+        self.iter().map(|h| vk::Handle::as_raw(*h)).collect()
+    }
+
     fn destroy(&self, deps: &Self::Dependencies) {
-        unsafe {
-            if deps.can_free {
-                let device = &deps.pool.dependencies().device;
-                let _ = device.free_descriptor_sets(*deps.pool, self.as_slice());
-            }
+        if deps.can_free {
+            let pool_deps = deps.pool.dependencies();
+            let _lock = pool_deps.lock();
+            let _ = unsafe {
+                pool_deps
+                    .device
+                    .free_descriptor_sets(*deps.pool, self.as_slice())
+            };
         }
     }
 }
 
 pub type DescriptorSets = Handle<Vec<vk::DescriptorSet>, Deps>;
+
+impl DescriptorSets {
+    /// Allocates one descriptor set per layout in `ds_layouts`.
+    pub fn allocate(pool: &DescriptorPool, ds_layouts: &[DescriptorSetLayout]) -> VkResult<Self> {
+        let (raw, deps) = allocate(pool, ds_layouts)?;
+        unsafe { Ok(Self::new(raw, deps)) }
+    }
+}
+
+fn allocate(
+    pool: &DescriptorPool,
+    ds_layouts: &[DescriptorSetLayout],
+) -> VkResult<(Vec<vk::DescriptorSet>, Deps)> {
+    let raw_layouts: Vec<vk::DescriptorSetLayout> = ds_layouts.iter().map(|l| **l).collect();
+    let ai = vk::DescriptorSetAllocateInfo::builder()
+        .descriptor_pool(**pool)
+        .set_layouts(&raw_layouts);
+
+    let pool_deps = pool.dependencies();
+    let raw = {
+        let _lock = pool_deps.lock();
+        unsafe { pool_deps.device.allocate_descriptor_sets(&ai)? }
+    };
+    let can_free = pool
+        .desc()
+        .flags
+        .contains(vk::DescriptorPoolCreateFlags::FREE_DESCRIPTOR_SET);
+    let deps = Deps {
+        pool: pool.clone(),
+        ds_layouts: ds_layouts.to_vec(),
+        can_free,
+    };
+    Ok((raw, deps))
+}
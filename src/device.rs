@@ -1,11 +1,51 @@
+use crate::features::DeviceFeatures;
 use crate::instance::Instance;
+use crate::physical_device::PhysicalDeviceInfo;
+use crate::queue::{self, Queue};
+use crate::registry::Owner;
+use crate::surface::Surface;
 use crate::{Handle, RawHandle};
+use ash::prelude::VkResult;
 use ash::version::DeviceV1_0;
 use ash::vk;
+use std::error::Error;
+use std::ffi::{CStr, CString};
+use std::fmt;
+use std::sync::{RwLock, RwLockReadGuard};
 
 pub struct Deps {
     pub instance: Instance,
     pub pdevice: vk::PhysicalDevice,
+    pub enabled_extensions: Vec<CString>,
+    pub enabled_features: DeviceFeatures,
+    /// Shared by operations of each queue, exclusive for `Device::wait_idle`.
+    queues: RwLock<()>,
+}
+
+impl Deps {
+    pub fn new(
+        instance: Instance,
+        pdevice: vk::PhysicalDevice,
+        enabled_extensions: Vec<CString>,
+        enabled_features: DeviceFeatures,
+    ) -> Self {
+        Self {
+            instance,
+            pdevice,
+            enabled_extensions,
+            enabled_features,
+            queues: RwLock::new(()),
+        }
+    }
+
+    /// Held by queue operations, which require external synchronization of queue.
+    pub(crate) fn queue_access(&self) -> RwLockReadGuard<'_, ()> {
+        self.queues.read().unwrap_or_else(|e| e.into_inner())
+    }
+
+    pub fn is_extension_enabled(&self, name: &CStr) -> bool {
+        self.enabled_extensions.iter().any(|e| e.as_c_str() == name)
+    }
 }
 
 impl RawHandle for ash::Device {
@@ -15,9 +55,323 @@ impl RawHandle for ash::Device {
         "device"
     }
 
+    fn owner(deps: &Self::Dependencies) -> Option<Owner> {
+        Some(Owner::of_instance(&deps.instance))
+    }
+
+    fn object_type() -> vk::ObjectType {
+        vk::ObjectType::DEVICE
+    }
+
+    fn raw_handles(&self) -> Vec<u64> {
+        vec![vk::Handle::as_raw(self.handle())]
+    }
+
     fn destroy(&self, _: &Self::Dependencies) {
         unsafe { self.destroy_device(None) }
     }
 }
 
 pub type Device = Handle<ash::Device, Deps>;
+
+impl Device {
+    /// Features, enabled on creation.
+    pub fn features(&self) -> &DeviceFeatures {
+        &self.dependencies().enabled_features
+    }
+
+    /// Waits for all queues of device to become idle.
+    /// Operations of `Queue`s are blocked meanwhile, as Vulkan requires.
+    pub fn wait_idle(&self) -> VkResult<()> {
+        let deps = self.dependencies();
+        let _queues = deps.queues.write().unwrap_or_else(|e| e.into_inner());
+        unsafe { self.device_wait_idle() }
+    }
+}
+
+/// Capability of requested queue.
+#[derive(Clone)]
+pub enum QueueCapability {
+    Graphics,
+    /// Prefers family without graphics support.
+    Compute,
+    /// Prefers family without graphics and compute support.
+    Transfer,
+    /// Prefers family of graphics queue, if it can present to surface.
+    Present(Surface),
+}
+
+impl fmt::Debug for QueueCapability {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            QueueCapability::Graphics => write!(f, "Graphics"),
+            QueueCapability::Compute => write!(f, "Compute"),
+            QueueCapability::Transfer => write!(f, "Transfer"),
+            QueueCapability::Present(_) => write!(f, "Present"),
+        }
+    }
+}
+
+struct QueueRequest {
+    capability: QueueCapability,
+    priority: f32,
+}
+
+/// Builder of `Device`, resolving queue requests to queue families.
+///
+/// Each request gets separate queue, while family has enough of them.
+/// Otherwise requests of one family share its last queue.
+pub struct DeviceBuilder {
+    pdevice: PhysicalDeviceInfo,
+    extensions: Vec<CString>,
+    features: DeviceFeatures,
+    optional_features: DeviceFeatures,
+    requests: Vec<QueueRequest>,
+}
+
+impl DeviceBuilder {
+    pub fn new(pdevice: &PhysicalDeviceInfo) -> Self {
+        Self {
+            pdevice: pdevice.clone(),
+            extensions: Vec::new(),
+            features: Default::default(),
+            optional_features: Default::default(),
+            requests: Vec::new(),
+        }
+    }
+
+    pub fn extension(mut self, name: &CStr) -> Self {
+        self.extensions.push(name.to_owned());
+        self
+    }
+
+    /// Required features. Adds to previously requested ones.
+    pub fn features(mut self, features: DeviceFeatures) -> Self {
+        self.features = self.features.union(&features);
+        self
+    }
+
+    /// Features, enabled only if supported by physical device.
+    pub fn optional_features(mut self, features: DeviceFeatures) -> Self {
+        self.optional_features = self.optional_features.union(&features);
+        self
+    }
+
+    /// Requests queue. `priority` is in `0.0..=1.0`.
+    pub fn queue(mut self, capability: QueueCapability, priority: f32) -> Self {
+        self.requests.push(QueueRequest {
+            capability,
+            priority,
+        });
+        self
+    }
+
+    /// Creates device and returns queues in order of requests.
+    pub fn build(self) -> Result<(Device, Vec<Queue>), BuildError> {
+        let mut missing_extensions: Vec<CString> = Vec::new();
+        for name in &self.extensions {
+            if !self.pdevice.supports_extension(name) && !missing_extensions.contains(name) {
+                missing_extensions.push(name.clone());
+            }
+        }
+        if !missing_extensions.is_empty() {
+            return Err(BuildError::MissingExtensions(missing_extensions));
+        }
+
+        // Features of extensions, promoted to Vulkan 1.2, need these extensions on Vulkan 1.1.
+        let instance = self.pdevice.instance();
+        let supported = unsafe {
+            self.pdevice
+                .features()
+                .enableable(instance, self.pdevice.handle(), &self.extensions)
+        };
+        let unsupported = self.features.unsupported(&supported);
+        if !unsupported.is_empty() {
+            return Err(BuildError::UnsupportedFeatures(unsupported));
+        }
+        let features = self
+            .features
+            .union(&self.optional_features.intersection(&supported));
+
+        let locations = self.plan_queues()?;
+
+        let mut families: Vec<(u32, Vec<f32>)> = Vec::new();
+        for (request, &(family, index)) in self.requests.iter().zip(&locations) {
+            let position = match families.iter().position(|(f, _)| *f == family) {
+                Some(position) => position,
+                None => {
+                    families.push((family, Vec::new()));
+                    families.len() - 1
+                }
+            };
+            let priorities = &mut families[position].1;
+            let index = index as usize;
+            if priorities.len() <= index {
+                priorities.resize(index + 1, 0.0);
+            }
+            priorities[index] = priorities[index].max(request.priority);
+        }
+
+        let queue_infos: Vec<_> = families
+            .iter()
+            .map(|(family, priorities)| {
+                vk::DeviceQueueCreateInfo::builder()
+                    .queue_family_index(*family)
+                    .queue_priorities(priorities)
+                    .build()
+            })
+            .collect();
+
+        let extension_ptrs: Vec<_> = self.extensions.iter().map(|e| e.as_ptr()).collect();
+        let ci = vk::DeviceCreateInfo::builder()
+            .queue_create_infos(&queue_infos)
+            .enabled_extension_names(&extension_ptrs);
+
+        let device = unsafe {
+            let raw = features.create_device(instance, self.pdevice.handle(), &ci)?;
+            let deps = Deps::new(
+                instance.clone(),
+                self.pdevice.handle(),
+                self.extensions,
+                features,
+            );
+            Device::new(raw, deps)
+        };
+
+        let mut created: Vec<Queue> = Vec::new();
+        let mut queues = Vec::with_capacity(locations.len());
+        for (family_index, queue_index) in locations {
+            let existing = created.iter().find(|q| {
+                let deps = q.dependencies();
+                deps.family_index == family_index && deps.queue_index == queue_index
+            });
+            let queue = match existing {
+                Some(queue) => queue.clone(),
+                None => unsafe {
+                    let raw = device.get_device_queue(family_index, queue_index);
+                    let deps = queue::Deps::new(device.clone(), family_index, queue_index);
+                    let queue = Queue::new(raw, deps);
+                    created.push(queue.clone());
+                    queue
+                },
+            };
+            queues.push(queue);
+        }
+
+        Ok((device, queues))
+    }
+
+    /// Family and queue index for each request.
+    fn plan_queues(&self) -> Result<Vec<(u32, u32)>, BuildError> {
+        let families = &self.pdevice.queue_families();
+        let mut used = vec![0u32; families.len()];
+        let mut graphics_family = None;
+        let mut locations = Vec::with_capacity(self.requests.len());
+
+        for request in &self.requests {
+            let family = self
+                .find_family(&request.capability, graphics_family)?
+                .ok_or_else(|| BuildError::NoQueueFamily(request.capability.clone()))?;
+
+            if let QueueCapability::Graphics = request.capability {
+                graphics_family.get_or_insert(family);
+            }
+
+            let count = families[family as usize].queue_count;
+            let family_used = &mut used[family as usize];
+            let index = (*family_used).min(count.saturating_sub(1));
+            *family_used += 1;
+            locations.push((family, index));
+        }
+
+        Ok(locations)
+    }
+
+    fn find_family(
+        &self,
+        capability: &QueueCapability,
+        graphics_family: Option<u32>,
+    ) -> Result<Option<u32>, BuildError> {
+        let graphics = vk::QueueFlags::GRAPHICS;
+        let compute = vk::QueueFlags::COMPUTE;
+        let transfer = vk::QueueFlags::TRANSFER;
+
+        let family = match capability {
+            QueueCapability::Graphics => self.preferred_family(graphics, &[]),
+            QueueCapability::Compute => self.preferred_family(compute, &[graphics]),
+            QueueCapability::Transfer => {
+                // Graphics and compute queues support transfers implicitly.
+                let any = self.preferred_family(transfer, &[graphics | compute, graphics]);
+                any.or_else(|| self.preferred_family(graphics, &[]))
+                    .or_else(|| self.preferred_family(compute, &[]))
+            }
+            QueueCapability::Present(surface) => {
+                let present = self.pdevice.present_families(surface)?;
+                match graphics_family {
+                    Some(family) if present.contains(&family) => Some(family),
+                    _ => present.first().copied(),
+                }
+            }
+        };
+        Ok(family)
+    }
+
+    /// First family with `required` flags and without `avoided` ones, tried in order of priority.
+    fn preferred_family(
+        &self,
+        required: vk::QueueFlags,
+        avoided: &[vk::QueueFlags],
+    ) -> Option<u32> {
+        let families = &self.pdevice.queue_families();
+        let candidates = || {
+            families
+                .iter()
+                .enumerate()
+                .filter(move |(_, f)| f.queue_count > 0 && f.queue_flags.contains(required))
+        };
+
+        for &avoid in avoided {
+            let dedicated = candidates().find(|(_, f)| !f.queue_flags.intersects(avoid));
+            if let Some((index, _)) = dedicated {
+                return Some(index as u32);
+            }
+        }
+        candidates().next().map(|(index, _)| index as u32)
+    }
+}
+
+#[derive(Debug)]
+pub enum BuildError {
+    NoQueueFamily(QueueCapability),
+    /// Names of extensions, not supported by physical device.
+    MissingExtensions(Vec<CString>),
+    /// Names of required features, not supported by physical device.
+    UnsupportedFeatures(Vec<&'static str>),
+    Vulkan(vk::Result),
+}
+
+impl From<vk::Result> for BuildError {
+    fn from(e: vk::Result) -> Self {
+        Self::Vulkan(e)
+    }
+}
+
+impl Error for BuildError {}
+
+impl fmt::Display for BuildError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BuildError::NoQueueFamily(capability) => {
+                write!(f, "No queue family with {:?} capability", capability)
+            }
+            BuildError::MissingExtensions(names) => {
+                let names: Vec<_> = names.iter().map(|n| n.to_string_lossy()).collect();
+                write!(f, "Missing device extensions: {}", names.join(", "))
+            }
+            BuildError::UnsupportedFeatures(names) => {
+                write!(f, "Unsupported device features: {}", names.join(", "))
+            }
+            BuildError::Vulkan(e) => write!(f, "Can't create device: {}", e),
+        }
+    }
+}
@@ -3,10 +3,24 @@ use crate::sampler::Sampler;
 use crate::{Handle, RawHandle};
 use ash::version::DeviceV1_0;
 use ash::vk;
+use std::error::Error;
+use std::fmt;
+
+/// Binding of descriptor set layout.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct DescriptorBinding {
+    pub binding: u32,
+    pub descriptor_type: vk::DescriptorType,
+    pub descriptor_count: u32,
+    pub stage_flags: vk::ShaderStageFlags,
+    /// Empty or of `descriptor_count` length.
+    pub immutable_samplers: Vec<Sampler>,
+}
 
 pub struct Deps {
     pub device: Device,
-    pub samplers: Vec<Sampler>,
+    /// Bindings, layout is created with. Keep immutable samplers alive.
+    pub bindings: Vec<DescriptorBinding>,
 }
 
 impl RawHandle for vk::DescriptorSetLayout {
@@ -16,6 +30,18 @@ impl RawHandle for vk::DescriptorSetLayout {
         "descriptor set layout"
     }
 
+    fn device(deps: &Self::Dependencies) -> Option<&Device> {
+        Some(&deps.device)
+    }
+
+    fn object_type() -> vk::ObjectType {
+        vk::ObjectType::DESCRIPTOR_SET_LAYOUT
+    }
+
+    fn raw_handles(&self) -> Vec<u64> {
+        vec![vk::Handle::as_raw(*self)]
+    }
+
     fn destroy(&self, dependencies: &Self::Dependencies) {
         unsafe {
             dependencies
@@ -26,3 +52,87 @@ impl RawHandle for vk::DescriptorSetLayout {
 }
 
 pub type DescriptorSetLayout = Handle<vk::DescriptorSetLayout, Deps>;
+
+impl DescriptorSetLayout {
+    pub fn create(device: &Device, bindings: &[DescriptorBinding]) -> Result<Self, CreateError> {
+        for binding in bindings {
+            let samplers = binding.immutable_samplers.len();
+            if samplers != 0 && samplers != binding.descriptor_count as usize {
+                return Err(CreateError::ImmutableSamplerCount {
+                    binding: binding.binding,
+                    descriptor_count: binding.descriptor_count,
+                    samplers,
+                });
+            }
+        }
+
+        let raw_samplers: Vec<Vec<vk::Sampler>> = bindings
+            .iter()
+            .map(|b| b.immutable_samplers.iter().map(|s| **s).collect())
+            .collect();
+
+        let raw_bindings: Vec<vk::DescriptorSetLayoutBinding> = bindings
+            .iter()
+            .zip(&raw_samplers)
+            .map(|(binding, samplers)| {
+                let mut raw = vk::DescriptorSetLayoutBinding::builder()
+                    .binding(binding.binding)
+                    .descriptor_type(binding.descriptor_type)
+                    .descriptor_count(binding.descriptor_count)
+                    .stage_flags(binding.stage_flags);
+                // Builder setter would replace descriptor count with sampler count.
+                if !samplers.is_empty() {
+                    raw.p_immutable_samplers = samplers.as_ptr();
+                }
+                raw.build()
+            })
+            .collect();
+
+        let ci = vk::DescriptorSetLayoutCreateInfo::builder().bindings(&raw_bindings);
+
+        unsafe {
+            let raw = device.create_descriptor_set_layout(&ci, None)?;
+            let deps = Deps {
+                device: device.clone(),
+                bindings: bindings.to_vec(),
+            };
+            Ok(Self::new(raw, deps))
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum CreateError {
+    /// Immutable samplers of binding are neither empty nor of `descriptor_count` length.
+    ImmutableSamplerCount {
+        binding: u32,
+        descriptor_count: u32,
+        samplers: usize,
+    },
+    Vulkan(vk::Result),
+}
+
+impl From<vk::Result> for CreateError {
+    fn from(e: vk::Result) -> Self {
+        Self::Vulkan(e)
+    }
+}
+
+impl Error for CreateError {}
+
+impl fmt::Display for CreateError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CreateError::ImmutableSamplerCount {
+                binding,
+                descriptor_count,
+                samplers,
+            } => write!(
+                f,
+                "Binding {} has {} immutable samplers for {} descriptors",
+                binding, samplers, descriptor_count
+            ),
+            CreateError::Vulkan(e) => write!(f, "Can't create descriptor set layout: {}", e),
+        }
+    }
+}
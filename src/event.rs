@@ -0,0 +1,80 @@
+use crate::device::Device;
+use crate::{Handle, RawHandle};
+use ash::prelude::VkResult;
+use ash::version::DeviceV1_0;
+use ash::vk;
+use std::sync::{Mutex, MutexGuard};
+
+pub struct Deps {
+    pub device: Device,
+    /// Synchronizes host access to event between clones.
+    lock: Mutex<()>,
+}
+
+impl Deps {
+    pub fn new(device: Device) -> Self {
+        Self {
+            device,
+            lock: Mutex::new(()),
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, ()> {
+        self.lock.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl RawHandle for vk::Event {
+    type Dependencies = Deps;
+
+    fn name() -> &'static str {
+        "event"
+    }
+
+    fn device(deps: &Self::Dependencies) -> Option<&Device> {
+        Some(&deps.device)
+    }
+
+    fn object_type() -> vk::ObjectType {
+        vk::ObjectType::EVENT
+    }
+
+    fn raw_handles(&self) -> Vec<u64> {
+        vec![vk::Handle::as_raw(*self)]
+    }
+
+    fn destroy(&self, dependencies: &Self::Dependencies) {
+        unsafe { dependencies.device.destroy_event(*self, None) }
+    }
+}
+
+pub type Event = Handle<vk::Event, Deps>;
+
+impl Event {
+    pub fn create(device: &Device) -> VkResult<Self> {
+        let ci = vk::EventCreateInfo::builder();
+        unsafe {
+            let raw = device.create_event(&ci, None)?;
+            Ok(Self::new(raw, Deps::new(device.clone())))
+        }
+    }
+
+    /// Sets event to signaled state from host.
+    pub fn set(&self) -> VkResult<()> {
+        let deps = self.dependencies();
+        let _lock = deps.lock();
+        unsafe { deps.device.set_event(**self) }
+    }
+
+    /// Sets event to unsignaled state from host.
+    pub fn reset(&self) -> VkResult<()> {
+        let deps = self.dependencies();
+        let _lock = deps.lock();
+        unsafe { deps.device.reset_event(**self) }
+    }
+
+    /// Returns `true` if event is signaled.
+    pub fn status(&self) -> VkResult<bool> {
+        unsafe { self.dependencies().device.get_event_status(**self) }
+    }
+}
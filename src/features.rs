@@ -0,0 +1,593 @@
+use crate::instance::Instance;
+use ash::extensions::khr;
+use ash::prelude::VkResult;
+use ash::version::{InstanceV1_0, InstanceV1_1};
+use ash::vk;
+use std::ffi::{c_void, CStr, CString};
+use std::ptr;
+use std::slice;
+
+#[derive(Default)]
+struct RawFeatures {
+    v1_0: vk::PhysicalDeviceFeatures,
+    v1_1: vk::PhysicalDeviceVulkan11Features,
+    v1_2: vk::PhysicalDeviceVulkan12Features,
+}
+
+macro_rules! device_features {
+    ($($group:ident { $($field:ident,)* })*) => {
+        /// Owned set of Vulkan 1.0, 1.1 and 1.2 device features.
+        ///
+        /// Field names match ones of `vk::PhysicalDeviceFeatures`,
+        /// `vk::PhysicalDeviceVulkan11Features` and `vk::PhysicalDeviceVulkan12Features`.
+        #[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash)]
+        pub struct DeviceFeatures {
+            $($(pub $field: bool,)*)*
+        }
+
+        impl DeviceFeatures {
+            /// Feature names with their values.
+            pub fn iter(&self) -> impl Iterator<Item = (&'static str, bool)> {
+                vec![$($((stringify!($field), self.$field),)*)*].into_iter()
+            }
+
+            /// Features, enabled in both sets.
+            pub fn intersection(&self, other: &Self) -> Self {
+                Self {
+                    $($($field: self.$field && other.$field,)*)*
+                }
+            }
+
+            /// Features, enabled in any of sets.
+            pub fn union(&self, other: &Self) -> Self {
+                Self {
+                    $($($field: self.$field || other.$field,)*)*
+                }
+            }
+
+            fn from_raw(raw: &RawFeatures) -> Self {
+                Self {
+                    $($($field: raw.$group.$field == vk::TRUE,)*)*
+                }
+            }
+
+            fn to_raw(self) -> RawFeatures {
+                let mut raw = RawFeatures::default();
+                $($(raw.$group.$field = self.$field as vk::Bool32;)*)*
+                raw
+            }
+        }
+    };
+}
+
+device_features! {
+    v1_0 {
+        robust_buffer_access,
+        full_draw_index_uint32,
+        image_cube_array,
+        independent_blend,
+        geometry_shader,
+        tessellation_shader,
+        sample_rate_shading,
+        dual_src_blend,
+        logic_op,
+        multi_draw_indirect,
+        draw_indirect_first_instance,
+        depth_clamp,
+        depth_bias_clamp,
+        fill_mode_non_solid,
+        depth_bounds,
+        wide_lines,
+        large_points,
+        alpha_to_one,
+        multi_viewport,
+        sampler_anisotropy,
+        texture_compression_etc2,
+        texture_compression_astc_ldr,
+        texture_compression_bc,
+        occlusion_query_precise,
+        pipeline_statistics_query,
+        vertex_pipeline_stores_and_atomics,
+        fragment_stores_and_atomics,
+        shader_tessellation_and_geometry_point_size,
+        shader_image_gather_extended,
+        shader_storage_image_extended_formats,
+        shader_storage_image_multisample,
+        shader_storage_image_read_without_format,
+        shader_storage_image_write_without_format,
+        shader_uniform_buffer_array_dynamic_indexing,
+        shader_sampled_image_array_dynamic_indexing,
+        shader_storage_buffer_array_dynamic_indexing,
+        shader_storage_image_array_dynamic_indexing,
+        shader_clip_distance,
+        shader_cull_distance,
+        shader_float64,
+        shader_int64,
+        shader_int16,
+        shader_resource_residency,
+        shader_resource_min_lod,
+        sparse_binding,
+        sparse_residency_buffer,
+        sparse_residency_image2_d,
+        sparse_residency_image3_d,
+        sparse_residency2_samples,
+        sparse_residency4_samples,
+        sparse_residency8_samples,
+        sparse_residency16_samples,
+        sparse_residency_aliased,
+        variable_multisample_rate,
+        inherited_queries,
+    }
+    v1_1 {
+        storage_buffer16_bit_access,
+        uniform_and_storage_buffer16_bit_access,
+        storage_push_constant16,
+        storage_input_output16,
+        multiview,
+        multiview_geometry_shader,
+        multiview_tessellation_shader,
+        variable_pointers_storage_buffer,
+        variable_pointers,
+        protected_memory,
+        sampler_ycbcr_conversion,
+        shader_draw_parameters,
+    }
+    v1_2 {
+        sampler_mirror_clamp_to_edge,
+        draw_indirect_count,
+        storage_buffer8_bit_access,
+        uniform_and_storage_buffer8_bit_access,
+        storage_push_constant8,
+        shader_buffer_int64_atomics,
+        shader_shared_int64_atomics,
+        shader_float16,
+        shader_int8,
+        descriptor_indexing,
+        shader_input_attachment_array_dynamic_indexing,
+        shader_uniform_texel_buffer_array_dynamic_indexing,
+        shader_storage_texel_buffer_array_dynamic_indexing,
+        shader_uniform_buffer_array_non_uniform_indexing,
+        shader_sampled_image_array_non_uniform_indexing,
+        shader_storage_buffer_array_non_uniform_indexing,
+        shader_storage_image_array_non_uniform_indexing,
+        shader_input_attachment_array_non_uniform_indexing,
+        shader_uniform_texel_buffer_array_non_uniform_indexing,
+        shader_storage_texel_buffer_array_non_uniform_indexing,
+        descriptor_binding_uniform_buffer_update_after_bind,
+        descriptor_binding_sampled_image_update_after_bind,
+        descriptor_binding_storage_image_update_after_bind,
+        descriptor_binding_storage_buffer_update_after_bind,
+        descriptor_binding_uniform_texel_buffer_update_after_bind,
+        descriptor_binding_storage_texel_buffer_update_after_bind,
+        descriptor_binding_update_unused_while_pending,
+        descriptor_binding_partially_bound,
+        descriptor_binding_variable_descriptor_count,
+        runtime_descriptor_array,
+        sampler_filter_minmax,
+        scalar_block_layout,
+        imageless_framebuffer,
+        uniform_buffer_standard_layout,
+        shader_subgroup_extended_types,
+        separate_depth_stencil_layouts,
+        host_query_reset,
+        timeline_semaphore,
+        buffer_device_address,
+        buffer_device_address_capture_replay,
+        buffer_device_address_multi_device,
+        vulkan_memory_model,
+        vulkan_memory_model_device_scope,
+        vulkan_memory_model_availability_visibility_chains,
+        shader_output_viewport_index,
+        shader_output_layer,
+        subgroup_broadcast_dynamic_id,
+    }
+}
+
+macro_rules! split_features {
+    ($($field:ident: $ty:ty => $group:ident { $($name:ident,)* })*) => {
+        /// Vulkan 1.1 features and features of extensions, promoted to Vulkan 1.2.
+        /// Chained instead of `vk::PhysicalDeviceVulkan11Features` and
+        /// `vk::PhysicalDeviceVulkan12Features` on Vulkan 1.1.
+        #[derive(Default)]
+        struct SplitFeatures {
+            $($field: $ty,)*
+        }
+
+        impl SplitFeatures {
+            fn from_raw(raw: &RawFeatures) -> Self {
+                let mut split = Self::default();
+                $($(split.$field.$name = raw.$group.$name;)*)*
+                split
+            }
+
+            fn write_to(&self, raw: &mut RawFeatures) {
+                $($(raw.$group.$name = self.$field.$name;)*)*
+            }
+        }
+    };
+}
+
+split_features! {
+    storage_16bit: vk::PhysicalDevice16BitStorageFeatures => v1_1 {
+        storage_buffer16_bit_access,
+        uniform_and_storage_buffer16_bit_access,
+        storage_push_constant16,
+        storage_input_output16,
+    }
+    multiview: vk::PhysicalDeviceMultiviewFeatures => v1_1 {
+        multiview,
+        multiview_geometry_shader,
+        multiview_tessellation_shader,
+    }
+    variable_pointers: vk::PhysicalDeviceVariablePointersFeatures => v1_1 {
+        variable_pointers_storage_buffer,
+        variable_pointers,
+    }
+    protected_memory: vk::PhysicalDeviceProtectedMemoryFeatures => v1_1 {
+        protected_memory,
+    }
+    sampler_ycbcr_conversion: vk::PhysicalDeviceSamplerYcbcrConversionFeatures => v1_1 {
+        sampler_ycbcr_conversion,
+    }
+    shader_draw_parameters: vk::PhysicalDeviceShaderDrawParametersFeatures => v1_1 {
+        shader_draw_parameters,
+    }
+    timeline_semaphore: vk::PhysicalDeviceTimelineSemaphoreFeaturesKHR => v1_2 {
+        timeline_semaphore,
+    }
+    descriptor_indexing: vk::PhysicalDeviceDescriptorIndexingFeaturesEXT => v1_2 {
+        shader_input_attachment_array_dynamic_indexing,
+        shader_uniform_texel_buffer_array_dynamic_indexing,
+        shader_storage_texel_buffer_array_dynamic_indexing,
+        shader_uniform_buffer_array_non_uniform_indexing,
+        shader_sampled_image_array_non_uniform_indexing,
+        shader_storage_buffer_array_non_uniform_indexing,
+        shader_storage_image_array_non_uniform_indexing,
+        shader_input_attachment_array_non_uniform_indexing,
+        shader_uniform_texel_buffer_array_non_uniform_indexing,
+        shader_storage_texel_buffer_array_non_uniform_indexing,
+        descriptor_binding_uniform_buffer_update_after_bind,
+        descriptor_binding_sampled_image_update_after_bind,
+        descriptor_binding_storage_image_update_after_bind,
+        descriptor_binding_storage_buffer_update_after_bind,
+        descriptor_binding_uniform_texel_buffer_update_after_bind,
+        descriptor_binding_storage_texel_buffer_update_after_bind,
+        descriptor_binding_update_unused_while_pending,
+        descriptor_binding_partially_bound,
+        descriptor_binding_variable_descriptor_count,
+        runtime_descriptor_array,
+    }
+}
+
+impl SplitFeatures {
+    /// Links structs of Vulkan 1.1 and of `extensions` before `next`. Returns head of chain.
+    /// `self` must not be moved while chain is used.
+    fn chain(&mut self, extensions: SplitExtensions, next: *mut c_void) -> *mut c_void {
+        macro_rules! link {
+            ($head:ident, $($features:expr),*) => {
+                $(
+                    $features.p_next = $head;
+                    $head = &mut $features as *mut _ as _;
+                )*
+            };
+        }
+
+        let mut head = next;
+        if extensions.timeline_semaphore {
+            link!(head, self.timeline_semaphore);
+        }
+        if extensions.descriptor_indexing {
+            link!(head, self.descriptor_indexing);
+        }
+        link!(
+            head,
+            self.storage_16bit,
+            self.multiview,
+            self.variable_pointers,
+            self.protected_memory,
+            self.sampler_ycbcr_conversion,
+            self.shader_draw_parameters
+        );
+        head
+    }
+}
+
+/// Extensions, which feature structs are chained on Vulkan 1.1.
+#[derive(Debug, Copy, Clone)]
+struct SplitExtensions {
+    timeline_semaphore: bool,
+    descriptor_indexing: bool,
+}
+
+/// Structs, chained into `vk::PhysicalDeviceFeatures2` and `vk::DeviceCreateInfo`.
+#[derive(Debug, Copy, Clone)]
+enum Chain {
+    /// Only `vk::PhysicalDeviceFeatures` on Vulkan 1.0.
+    None,
+    /// `SplitFeatures` on Vulkan 1.1.
+    Split(SplitExtensions),
+    /// `vk::PhysicalDeviceVulkan11Features` and `vk::PhysicalDeviceVulkan12Features`
+    /// on Vulkan 1.2.
+    Core,
+}
+
+impl Chain {
+    /// `has_extension` checks device extensions, which feature structs may be chained.
+    unsafe fn new(
+        instance: &Instance,
+        pdevice: vk::PhysicalDevice,
+        has_extension: impl Fn(&CStr) -> bool,
+    ) -> Self {
+        match api_version(instance, pdevice) {
+            v if v >= vk::API_VERSION_1_2 => Chain::Core,
+            v if v >= vk::API_VERSION_1_1 => Chain::Split(SplitExtensions {
+                timeline_semaphore: has_extension(khr::TimelineSemaphore::name()),
+                descriptor_indexing: has_extension(vk::ExtDescriptorIndexingFn::name()),
+            }),
+            _ => Chain::None,
+        }
+    }
+}
+
+impl Chain {
+    /// Copy of `raw` with features, which structs are not chained, cleared.
+    fn retain_chained(self, raw: &RawFeatures) -> RawFeatures {
+        let mut chained = RawFeatures {
+            v1_0: raw.v1_0,
+            ..Default::default()
+        };
+        match self {
+            Chain::None => {}
+            Chain::Split(extensions) => {
+                let mut split = SplitFeatures::from_raw(raw);
+                if !extensions.timeline_semaphore {
+                    split.timeline_semaphore = Default::default();
+                }
+                if !extensions.descriptor_indexing {
+                    split.descriptor_indexing = Default::default();
+                }
+                split.write_to(&mut chained);
+            }
+            Chain::Core => {
+                chained.v1_1 = raw.v1_1;
+                chained.v1_2 = raw.v1_2;
+            }
+        }
+        chained
+    }
+}
+
+/// Vulkan version, supported by both `instance` and `pdevice`.
+pub(crate) unsafe fn api_version(instance: &Instance, pdevice: vk::PhysicalDevice) -> u32 {
+    let device_version = instance.get_physical_device_properties(pdevice).api_version;
+    let instance_version = instance.dependencies().api_version;
+    device_version.min(instance_version)
+}
+
+impl DeviceFeatures {
+    /// Features, supported by `pdevice`.
+    ///
+    /// Vulkan 1.1 features are queried if both instance and device support Vulkan 1.1.
+    /// Vulkan 1.2 features are queried if both support Vulkan 1.2. On Vulkan 1.1 features of
+    /// `VK_KHR_timeline_semaphore` and `VK_EXT_descriptor_indexing` are queried instead,
+    /// if device supports them.
+    pub fn query(instance: &Instance, pdevice: vk::PhysicalDevice) -> Self {
+        let mut raw = RawFeatures::default();
+        unsafe {
+            let supported_extensions = instance
+                .enumerate_device_extension_properties(pdevice)
+                .unwrap_or_default();
+            let has_extension = |name: &CStr| {
+                supported_extensions
+                    .iter()
+                    .any(|e| CStr::from_ptr(e.extension_name.as_ptr()) == name)
+            };
+
+            let mut split = SplitFeatures::default();
+            let mut features2 = vk::PhysicalDeviceFeatures2::default();
+            let chain = Chain::new(instance, pdevice, has_extension);
+            match chain {
+                Chain::None => {
+                    raw.v1_0 = instance.get_physical_device_features(pdevice);
+                    return Self::from_raw(&raw);
+                }
+                Chain::Split(extensions) => {
+                    features2.p_next = split.chain(extensions, ptr::null_mut());
+                }
+                Chain::Core => {
+                    raw.v1_1.p_next = &mut raw.v1_2 as *mut _ as _;
+                    features2.p_next = &mut raw.v1_1 as *mut _ as _;
+                }
+            }
+
+            instance.get_physical_device_features2(pdevice, &mut features2);
+            raw.v1_1.p_next = ptr::null_mut();
+            raw.v1_0 = features2.features;
+            if let Chain::Split(_) = chain {
+                split.write_to(&mut raw);
+            }
+        }
+        Self::from_raw(&raw)
+    }
+
+    /// Names of features, enabled in `self`, but not in `supported`.
+    pub fn unsupported(&self, supported: &Self) -> Vec<&'static str> {
+        self.iter()
+            .zip(supported.iter())
+            .filter(|((_, requested), (_, supported))| *requested && !*supported)
+            .map(|((name, _), _)| name)
+            .collect()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.iter().all(|(_, enabled)| !enabled)
+    }
+
+    /// Features of `self`, which can be enabled on `pdevice` with `extensions` enabled.
+    /// Features, which structs are not chained by `create_device`, are cleared.
+    pub(crate) unsafe fn enableable(
+        &self,
+        instance: &Instance,
+        pdevice: vk::PhysicalDevice,
+        extensions: &[CString],
+    ) -> Self {
+        let has_extension = |name: &CStr| extensions.iter().any(|e| e.as_c_str() == name);
+        let chain = Chain::new(instance, pdevice, has_extension);
+        Self::from_raw(&chain.retain_chained(&self.to_raw()))
+    }
+
+    /// Creates device with `self` features enabled.
+    /// Fails with `vk::Result::ERROR_EXTENSION_NOT_PRESENT` if some of features can't be chained,
+    /// e.g. Vulkan 1.2 features on Vulkan 1.1 device without extensions, enabled by `ci`.
+    ///
+    /// # Safety
+    /// * `ci` must be valid and must not enable features itself.
+    pub(crate) unsafe fn create_device(
+        &self,
+        instance: &Instance,
+        pdevice: vk::PhysicalDevice,
+        ci: &vk::DeviceCreateInfo,
+    ) -> VkResult<ash::Device> {
+        let mut raw = self.to_raw();
+        let mut split = SplitFeatures::from_raw(&raw);
+        let mut ci = *ci;
+
+        let enabled_extensions = match ci.pp_enabled_extension_names.is_null() {
+            true => &[],
+            false => slice::from_raw_parts(
+                ci.pp_enabled_extension_names,
+                ci.enabled_extension_count as usize,
+            ),
+        };
+        let has_extension = |name: &CStr| {
+            enabled_extensions
+                .iter()
+                .any(|&e| CStr::from_ptr(e) == name)
+        };
+
+        let chain = Chain::new(instance, pdevice, has_extension);
+        if Self::from_raw(&chain.retain_chained(&raw)) != *self {
+            return Err(vk::Result::ERROR_EXTENSION_NOT_PRESENT);
+        }
+
+        match chain {
+            Chain::None => {}
+            Chain::Split(extensions) => {
+                ci.p_next = split.chain(extensions, ci.p_next as _);
+            }
+            Chain::Core => {
+                raw.v1_2.p_next = ci.p_next as _;
+                raw.v1_1.p_next = &mut raw.v1_2 as *mut _ as _;
+                ci.p_next = &raw.v1_1 as *const _ as _;
+            }
+        }
+        ci.p_enabled_features = &raw.v1_0;
+        instance.create_device(pdevice, &ci, None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn features(names: &[&str]) -> DeviceFeatures {
+        let mut features = DeviceFeatures::default();
+        for &name in names {
+            match name {
+                "geometry_shader" => features.geometry_shader = true,
+                "multiview" => features.multiview = true,
+                "timeline_semaphore" => features.timeline_semaphore = true,
+                "runtime_descriptor_array" => features.runtime_descriptor_array = true,
+                "draw_indirect_count" => features.draw_indirect_count = true,
+                _ => unreachable!("Unknown feature {}", name),
+            }
+        }
+        features
+    }
+
+    fn enabled(features: &DeviceFeatures) -> Vec<&'static str> {
+        features
+            .iter()
+            .filter(|(_, enabled)| *enabled)
+            .map(|(name, _)| name)
+            .collect()
+    }
+
+    #[test]
+    fn union_and_intersection() {
+        let a = features(&["geometry_shader", "multiview"]);
+        let b = features(&["multiview", "timeline_semaphore"]);
+
+        let union = enabled(&a.union(&b));
+        assert_eq!(
+            union,
+            ["geometry_shader", "multiview", "timeline_semaphore"]
+        );
+        assert_eq!(enabled(&a.intersection(&b)), ["multiview"]);
+        assert!(a.intersection(&DeviceFeatures::default()).is_empty());
+    }
+
+    #[test]
+    fn unsupported_lists_missing_features() {
+        let requested = features(&["geometry_shader", "timeline_semaphore"]);
+        let supported = features(&["geometry_shader", "multiview"]);
+
+        assert_eq!(requested.unsupported(&supported), ["timeline_semaphore"]);
+        assert!(requested.unsupported(&requested).is_empty());
+        assert!(DeviceFeatures::default().unsupported(&supported).is_empty());
+    }
+
+    #[test]
+    fn raw_round_trip() {
+        let features = features(&["geometry_shader", "multiview", "timeline_semaphore"]);
+        assert_eq!(DeviceFeatures::from_raw(&features.to_raw()), features);
+    }
+
+    #[test]
+    fn vulkan_1_0_retains_only_core_features() {
+        let all = features(&["geometry_shader", "multiview", "timeline_semaphore"]);
+        let chained = Chain::None.retain_chained(&all.to_raw());
+        assert_eq!(
+            enabled(&DeviceFeatures::from_raw(&chained)),
+            ["geometry_shader"]
+        );
+    }
+
+    #[test]
+    fn vulkan_1_1_retains_features_of_enabled_extensions() {
+        let all = features(&[
+            "multiview",
+            "timeline_semaphore",
+            "runtime_descriptor_array",
+            "draw_indirect_count",
+        ]);
+        let chain = |timeline_semaphore, descriptor_indexing| {
+            let chain = Chain::Split(SplitExtensions {
+                timeline_semaphore,
+                descriptor_indexing,
+            });
+            DeviceFeatures::from_raw(&chain.retain_chained(&all.to_raw()))
+        };
+
+        assert_eq!(enabled(&chain(false, false)), ["multiview"]);
+        assert_eq!(
+            enabled(&chain(true, false)),
+            ["multiview", "timeline_semaphore"]
+        );
+        assert_eq!(
+            enabled(&chain(true, true)),
+            [
+                "multiview",
+                "runtime_descriptor_array",
+                "timeline_semaphore"
+            ]
+        );
+    }
+
+    #[test]
+    fn vulkan_1_2_retains_all_features() {
+        let all = features(&["multiview", "timeline_semaphore", "draw_indirect_count"]);
+        let chained = Chain::Core.retain_chained(&all.to_raw());
+        assert_eq!(DeviceFeatures::from_raw(&chained), all);
+    }
+}
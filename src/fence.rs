@@ -1,10 +1,29 @@
 use crate::device::Device;
-use crate::{Handle, RawHandle};
+use crate::{Handle, RawHandle, WaitResult};
+use ash::prelude::VkResult;
 use ash::version::DeviceV1_0;
 use ash::vk;
+use std::error::Error;
+use std::fmt;
+use std::sync::{Mutex, MutexGuard};
 
 pub struct Deps {
     pub device: Device,
+    /// Synchronizes access to fence between clones, where Vulkan requires it.
+    lock: Mutex<()>,
+}
+
+impl Deps {
+    pub fn new(device: Device) -> Self {
+        Self {
+            device,
+            lock: Mutex::new(()),
+        }
+    }
+
+    pub(crate) fn lock(&self) -> MutexGuard<'_, ()> {
+        self.lock.lock().unwrap_or_else(|e| e.into_inner())
+    }
 }
 
 impl RawHandle for vk::Fence {
@@ -14,9 +33,123 @@ impl RawHandle for vk::Fence {
         "fence"
     }
 
+    fn device(deps: &Self::Dependencies) -> Option<&Device> {
+        Some(&deps.device)
+    }
+
+    fn object_type() -> vk::ObjectType {
+        vk::ObjectType::FENCE
+    }
+
+    fn raw_handles(&self) -> Vec<u64> {
+        vec![vk::Handle::as_raw(*self)]
+    }
+
     fn destroy(&self, dependencies: &Self::Dependencies) {
         unsafe { dependencies.device.destroy_fence(*self, None) }
     }
 }
 
 pub type Fence = Handle<vk::Fence, Deps>;
+
+impl Fence {
+    pub fn create(device: &Device, signaled: bool) -> VkResult<Self> {
+        let flags = match signaled {
+            true => vk::FenceCreateFlags::SIGNALED,
+            false => vk::FenceCreateFlags::empty(),
+        };
+        let ci = vk::FenceCreateInfo::builder().flags(flags);
+
+        unsafe {
+            let raw = device.create_fence(&ci, None)?;
+            Ok(Self::new(raw, Deps::new(device.clone())))
+        }
+    }
+
+    /// Waits for fence to become signaled. `timeout` is in nanoseconds.
+    pub fn wait(&self, timeout: u64) -> VkResult<WaitResult> {
+        let device = &self.dependencies().device;
+        wait_for_raw_fences(device, &[**self], true, timeout)
+    }
+
+    /// Sets fence to unsignaled state.
+    pub fn reset(&self) -> VkResult<()> {
+        let deps = self.dependencies();
+        let _lock = deps.lock();
+        unsafe { deps.device.reset_fences(&[**self]) }
+    }
+
+    /// Returns `true` if fence is signaled. Doesn't wait.
+    pub fn is_signaled(&self) -> VkResult<bool> {
+        unsafe { self.dependencies().device.get_fence_status(**self) }
+    }
+}
+
+/// Waits for all `fences` to become signaled. `timeout` is in nanoseconds.
+pub fn wait_all(fences: &[Fence], timeout: u64) -> Result<WaitResult, WaitError> {
+    wait_for_fences(fences, true, timeout)
+}
+
+/// Waits for any of `fences` to become signaled. `timeout` is in nanoseconds.
+pub fn wait_any(fences: &[Fence], timeout: u64) -> Result<WaitResult, WaitError> {
+    wait_for_fences(fences, false, timeout)
+}
+
+fn wait_for_fences(
+    fences: &[Fence],
+    wait_all: bool,
+    timeout: u64,
+) -> Result<WaitResult, WaitError> {
+    let device = match fences.first() {
+        Some(fence) => &fence.dependencies().device,
+        None => return Ok(WaitResult::Signaled),
+    };
+
+    let raw_device = device.handle().handle();
+    let same_device = fences
+        .iter()
+        .all(|f| f.dependencies().device.handle().handle() == raw_device);
+    if !same_device {
+        return Err(WaitError::DifferentDevices);
+    }
+
+    let raw_fences: Vec<vk::Fence> = fences.iter().map(|f| **f).collect();
+    Ok(wait_for_raw_fences(device, &raw_fences, wait_all, timeout)?)
+}
+
+fn wait_for_raw_fences(
+    device: &Device,
+    fences: &[vk::Fence],
+    wait_all: bool,
+    timeout: u64,
+) -> VkResult<WaitResult> {
+    match unsafe { device.wait_for_fences(fences, wait_all, timeout) } {
+        Ok(()) => Ok(WaitResult::Signaled),
+        Err(vk::Result::TIMEOUT) => Ok(WaitResult::Timeout),
+        Err(e) => Err(e),
+    }
+}
+
+#[derive(Debug)]
+pub enum WaitError {
+    /// Fences are created by different devices.
+    DifferentDevices,
+    Vulkan(vk::Result),
+}
+
+impl From<vk::Result> for WaitError {
+    fn from(e: vk::Result) -> Self {
+        Self::Vulkan(e)
+    }
+}
+
+impl Error for WaitError {}
+
+impl fmt::Display for WaitError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            WaitError::DifferentDevices => write!(f, "Can't wait for fences of different devices"),
+            WaitError::Vulkan(e) => write!(f, "Can't wait for fences: {}", e),
+        }
+    }
+}
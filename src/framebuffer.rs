@@ -0,0 +1,183 @@
+use crate::device::Device;
+use crate::image_view::ImageView;
+use crate::render_pass::RenderPass;
+use crate::{Handle, RawHandle};
+use ash::version::DeviceV1_0;
+use ash::vk;
+use std::error::Error;
+use std::fmt;
+
+/// Attachment description of imageless framebuffer (VK_KHR_imageless_framebuffer).
+#[derive(Debug, Clone, Default)]
+pub struct AttachmentImageInfo {
+    pub flags: vk::ImageCreateFlags,
+    pub usage: vk::ImageUsageFlags,
+    pub width: u32,
+    pub height: u32,
+    pub layer_count: u32,
+    pub view_formats: Vec<vk::Format>,
+}
+
+impl AttachmentImageInfo {
+    /// Info to chain into `vk::FramebufferAttachmentsCreateInfo`.
+    pub fn raw(&self) -> vk::FramebufferAttachmentImageInfoBuilder<'_> {
+        vk::FramebufferAttachmentImageInfo::builder()
+            .flags(self.flags)
+            .usage(self.usage)
+            .width(self.width)
+            .height(self.height)
+            .layer_count(self.layer_count)
+            .view_formats(&self.view_formats)
+    }
+}
+
+pub enum Attachments {
+    /// Image views, bound to framebuffer at creation.
+    Views(Vec<ImageView>),
+    /// Imageless framebuffer. Views are provided at render pass begin.
+    Imageless(Vec<AttachmentImageInfo>),
+}
+
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash)]
+pub struct FramebufferDesc {
+    pub flags: vk::FramebufferCreateFlags,
+    pub width: u32,
+    pub height: u32,
+    pub layers: u32,
+}
+
+pub struct Deps {
+    pub render_pass: RenderPass,
+    pub attachments: Attachments,
+    pub desc: FramebufferDesc,
+}
+
+impl RawHandle for vk::Framebuffer {
+    type Dependencies = Deps;
+
+    fn name() -> &'static str {
+        "framebuffer"
+    }
+
+    fn device(deps: &Self::Dependencies) -> Option<&Device> {
+        Some(&deps.render_pass.dependencies().device)
+    }
+
+    fn object_type() -> vk::ObjectType {
+        vk::ObjectType::FRAMEBUFFER
+    }
+
+    fn raw_handles(&self) -> Vec<u64> {
+        vec![vk::Handle::as_raw(*self)]
+    }
+
+    fn destroy(&self, deps: &Self::Dependencies) {
+        let device = &deps.render_pass.dependencies().device;
+        unsafe { device.destroy_framebuffer(*self, None) }
+    }
+}
+
+pub type Framebuffer = Handle<vk::Framebuffer, Deps>;
+
+impl Framebuffer {
+    pub fn create(
+        render_pass: &RenderPass,
+        attachments: Vec<ImageView>,
+        desc: &FramebufferDesc,
+    ) -> Result<Self, CreateError> {
+        let device = render_pass.dependencies().device.handle().handle();
+        let position = attachments
+            .iter()
+            .position(|v| v.dependencies().image.device().handle().handle() != device);
+        if let Some(index) = position {
+            return Err(CreateError::DifferentDevice { attachment: index });
+        }
+
+        let raw_attachments: Vec<vk::ImageView> = attachments.iter().map(|v| **v).collect();
+        let ci = vk::FramebufferCreateInfo::builder()
+            .flags(desc.flags)
+            .render_pass(**render_pass)
+            .attachments(&raw_attachments)
+            .width(desc.width)
+            .height(desc.height)
+            .layers(desc.layers);
+
+        Self::create_raw(render_pass, &ci, Attachments::Views(attachments), desc)
+    }
+
+    /// Creates imageless framebuffer. `vk::FramebufferCreateFlags::IMAGELESS` is added to flags.
+    pub fn create_imageless(
+        render_pass: &RenderPass,
+        attachments: Vec<AttachmentImageInfo>,
+        desc: &FramebufferDesc,
+    ) -> Result<Self, CreateError> {
+        let raw_infos: Vec<vk::FramebufferAttachmentImageInfo> =
+            attachments.iter().map(|a| a.raw().build()).collect();
+        let mut attachments_ci =
+            vk::FramebufferAttachmentsCreateInfo::builder().attachment_image_infos(&raw_infos);
+        let mut ci = vk::FramebufferCreateInfo::builder()
+            .flags(desc.flags | vk::FramebufferCreateFlags::IMAGELESS)
+            .render_pass(**render_pass)
+            .width(desc.width)
+            .height(desc.height)
+            .layers(desc.layers)
+            .push_next(&mut attachments_ci);
+        ci.attachment_count = raw_infos.len() as u32;
+
+        let mut desc = *desc;
+        desc.flags |= vk::FramebufferCreateFlags::IMAGELESS;
+        Self::create_raw(render_pass, &ci, Attachments::Imageless(attachments), &desc)
+    }
+
+    fn create_raw(
+        render_pass: &RenderPass,
+        ci: &vk::FramebufferCreateInfo,
+        attachments: Attachments,
+        desc: &FramebufferDesc,
+    ) -> Result<Self, CreateError> {
+        let device = &render_pass.dependencies().device;
+        unsafe {
+            let raw = device.create_framebuffer(ci, None)?;
+            let deps = Deps {
+                render_pass: render_pass.clone(),
+                attachments,
+                desc: *desc,
+            };
+            Ok(Self::new(raw, deps))
+        }
+    }
+
+    pub fn desc(&self) -> &FramebufferDesc {
+        &self.dependencies().desc
+    }
+}
+
+#[derive(Debug)]
+pub enum CreateError {
+    /// Attachment view at index is created by other device than render pass.
+    DifferentDevice {
+        attachment: usize,
+    },
+    Vulkan(vk::Result),
+}
+
+impl From<vk::Result> for CreateError {
+    fn from(e: vk::Result) -> Self {
+        Self::Vulkan(e)
+    }
+}
+
+impl Error for CreateError {}
+
+impl fmt::Display for CreateError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CreateError::DifferentDevice { attachment } => write!(
+                f,
+                "Attachment {} is created by other device than render pass",
+                attachment
+            ),
+            CreateError::Vulkan(e) => write!(f, "Can't create framebuffer: {}", e),
+        }
+    }
+}
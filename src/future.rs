@@ -0,0 +1,345 @@
+use crate::device::Device;
+use crate::fence::Fence;
+use crate::semaphore::{TimelineApi, TimelineSemaphore};
+use ash::prelude::VkResult;
+use ash::version::{DeviceV1_0, DeviceV1_2};
+use ash::vk;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+/// Background thread, waiting for fences and timeline semaphores of one device.
+///
+/// All outstanding fences are waited by a single `vkWaitForFences` call in `wait any` mode.
+/// `poll_interval` bounds the time a newly added wait is delayed by an already running call.
+///
+/// Dropping `Waiter` blocks until all waited objects are signaled or device is lost, so they
+/// are not destroyed while used by GPU. Fence, which is never submitted or is reset while
+/// waited, blocks the drop forever.
+pub struct Waiter {
+    device: Device,
+    worker: Worker,
+}
+
+impl Waiter {
+    pub fn new(device: Device) -> Self {
+        Self::with_poll_interval(device, Duration::from_millis(1))
+    }
+
+    pub fn with_poll_interval(device: Device, poll_interval: Duration) -> Self {
+        let timeout = poll_interval.as_nanos() as u64;
+        let thread_device = device.clone();
+        let worker = Worker::spawn(move |pending| wait_any(&thread_device, pending, timeout));
+        Self { device, worker }
+    }
+
+    /// Returns future, resolved when `fence` becomes signaled.
+    /// `fence` is kept alive until then, even if the future is dropped.
+    ///
+    /// # Panics
+    /// * if `fence` is created by other device.
+    pub fn fence(&self, fence: Fence) -> WaitFuture {
+        self.check_device(&fence.dependencies().device);
+        self.worker.push(Target::Fence(fence))
+    }
+
+    /// Returns future, resolved when counter of `semaphore` reaches `value`.
+    /// `semaphore` is kept alive until then, even if the future is dropped.
+    ///
+    /// # Panics
+    /// * if `semaphore` is created by other device.
+    pub fn timeline(&self, semaphore: TimelineSemaphore, value: u64) -> WaitFuture {
+        self.check_device(&semaphore.dependencies().device);
+        self.worker.push(Target::Timeline(semaphore, value))
+    }
+
+    fn check_device(&self, device: &Device) {
+        let same_device = device.handle().handle() == self.device.handle().handle();
+        assert!(same_device, "Can't wait for object of other device");
+    }
+}
+
+/// Thread, resolving entries. `wait` blocks until any of pending entries may be complete.
+struct Worker {
+    sender: Option<Sender<Entry>>,
+    stopped: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Worker {
+    fn spawn(wait: impl FnMut(&[Entry]) + Send + 'static) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        let stopped = Arc::new(AtomicBool::new(false));
+        let thread_stopped = stopped.clone();
+        let thread = thread::Builder::new()
+            .name("vk_raii waiter".into())
+            .spawn(move || wait_loop(receiver, &thread_stopped, wait))
+            .expect("Can't spawn waiter thread");
+        Self {
+            sender: Some(sender),
+            stopped,
+            thread: Some(thread),
+        }
+    }
+
+    fn push(&self, target: Target) -> WaitFuture {
+        let shared = Arc::new(Mutex::new(Shared::default()));
+        let entry = Entry {
+            target,
+            shared: shared.clone(),
+        };
+
+        let sender = self.sender.as_ref().expect("Sender is taken only on drop");
+        if let Err(mpsc::SendError(entry)) = sender.send(entry) {
+            entry.resolve(Err(vk::Result::ERROR_DEVICE_LOST));
+        }
+
+        WaitFuture { shared }
+    }
+}
+
+impl Drop for Worker {
+    fn drop(&mut self) {
+        self.stopped.store(true, Ordering::Release);
+        // Wakes thread, blocked on empty channel.
+        self.sender.take();
+        if let Some(thread) = self.thread.take() {
+            if thread.join().is_err() {
+                log::error!("Waiter thread panicked");
+            }
+        }
+    }
+}
+
+/// Future, resolved when waited object is signaled. Can be dropped at any moment.
+pub struct WaitFuture {
+    shared: Arc<Mutex<Shared>>,
+}
+
+impl Future for WaitFuture {
+    type Output = VkResult<()>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut shared = self.shared.lock().expect("Waiter state poisoned");
+        match shared.result {
+            Some(result) => Poll::Ready(result),
+            None => {
+                shared.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+#[derive(Default)]
+struct Shared {
+    result: Option<VkResult<()>>,
+    waker: Option<Waker>,
+}
+
+enum Target {
+    Fence(Fence),
+    Timeline(TimelineSemaphore, u64),
+    #[cfg(test)]
+    Mock(Arc<AtomicBool>),
+}
+
+struct Entry {
+    target: Target,
+    shared: Arc<Mutex<Shared>>,
+}
+
+impl Entry {
+    /// Checks status of target and resolves future, if it's ready. Returns `true` if resolved.
+    fn try_complete(&self) -> bool {
+        let status = match &self.target {
+            Target::Fence(fence) => fence.is_signaled(),
+            Target::Timeline(semaphore, value) => semaphore.counter_value().map(|v| v >= *value),
+            #[cfg(test)]
+            Target::Mock(signaled) => Ok(signaled.load(Ordering::Acquire)),
+        };
+
+        let result = match status {
+            Ok(false) => return false,
+            Ok(true) => Ok(()),
+            Err(e) => Err(e),
+        };
+        self.resolve(result);
+        true
+    }
+
+    fn resolve(&self, result: VkResult<()>) {
+        let mut shared = self.shared.lock().expect("Waiter state poisoned");
+        shared.result = Some(result);
+        if let Some(waker) = shared.waker.take() {
+            waker.wake()
+        }
+    }
+}
+
+fn wait_loop(receiver: Receiver<Entry>, stopped: &AtomicBool, mut wait: impl FnMut(&[Entry])) {
+    let mut pending: Vec<Entry> = Vec::new();
+
+    while !stopped.load(Ordering::Acquire) {
+        if pending.is_empty() {
+            match receiver.recv() {
+                Ok(entry) => pending.push(entry),
+                Err(_) => break,
+            }
+        }
+
+        pending.extend(receiver.try_iter());
+
+        wait(&pending);
+        pending.retain(|entry| !entry.try_complete());
+    }
+
+    // Waited objects may still be used by GPU, so they are released only after completion.
+    pending.extend(receiver.try_iter());
+    pending.retain(|entry| !entry.try_complete());
+    while !pending.is_empty() {
+        wait(&pending);
+        pending.retain(|entry| !entry.try_complete());
+    }
+
+    log::trace!("Waiter thread finished");
+}
+
+/// Waits until any of `pending` objects is signaled or `timeout` expires.
+fn wait_any(device: &Device, pending: &[Entry], timeout: u64) {
+    let fences: Vec<vk::Fence> = pending
+        .iter()
+        .filter_map(|entry| match &entry.target {
+            Target::Fence(fence) => Some(**fence),
+            _ => None,
+        })
+        .collect();
+
+    if !fences.is_empty() {
+        let _ = unsafe { device.wait_for_fences(&fences, false, timeout) };
+        return;
+    }
+
+    let mut api = None;
+    let mut semaphores = Vec::new();
+    let mut values = Vec::new();
+    for entry in pending {
+        if let Target::Timeline(semaphore, value) = &entry.target {
+            api.get_or_insert(&semaphore.dependencies().api);
+            semaphores.push(*semaphore.handle().handle());
+            values.push(*value);
+        }
+    }
+
+    let info = vk::SemaphoreWaitInfo::builder()
+        .flags(vk::SemaphoreWaitFlags::ANY)
+        .semaphores(&semaphores)
+        .values(&values);
+
+    let _ = unsafe {
+        match api {
+            Some(TimelineApi::Core) => device.wait_semaphores(&info, timeout),
+            Some(TimelineApi::Khr(loader)) => {
+                loader.wait_semaphores(device.handle().handle(), &info, timeout)
+            }
+            None => Ok(()),
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::task::Wake;
+    use std::thread::Thread;
+
+    struct ThreadWaker(Thread);
+
+    impl Wake for ThreadWaker {
+        fn wake(self: Arc<Self>) {
+            self.0.unpark()
+        }
+    }
+
+    fn block_on(mut future: WaitFuture) -> VkResult<()> {
+        let waker = Arc::new(ThreadWaker(thread::current())).into();
+        let mut cx = Context::from_waker(&waker);
+        loop {
+            match Pin::new(&mut future).poll(&mut cx) {
+                Poll::Ready(result) => return result,
+                Poll::Pending => thread::park(),
+            }
+        }
+    }
+
+    fn is_ready(future: &WaitFuture) -> bool {
+        future.shared.lock().unwrap().result.is_some()
+    }
+
+    fn worker() -> Worker {
+        Worker::spawn(|_| thread::sleep(Duration::from_millis(1)))
+    }
+
+    fn signal_later(signaled: &Arc<AtomicBool>, delay: Duration) -> JoinHandle<()> {
+        let signaled = signaled.clone();
+        thread::spawn(move || {
+            thread::sleep(delay);
+            signaled.store(true, Ordering::Release);
+        })
+    }
+
+    #[test]
+    fn future_resolves_when_signaled() {
+        let worker = worker();
+        let signaled = Arc::new(AtomicBool::new(false));
+        let future = worker.push(Target::Mock(signaled.clone()));
+        assert!(!is_ready(&future));
+
+        let signaler = signal_later(&signaled, Duration::from_millis(10));
+        assert_eq!(block_on(future), Ok(()));
+        signaler.join().unwrap();
+    }
+
+    #[test]
+    fn target_is_released_after_completion() {
+        let worker = worker();
+        let signaled = Arc::new(AtomicBool::new(false));
+        drop(worker.push(Target::Mock(signaled.clone())));
+        assert_eq!(Arc::strong_count(&signaled), 2);
+
+        signaled.store(true, Ordering::Release);
+        while Arc::strong_count(&signaled) > 1 {
+            thread::sleep(Duration::from_millis(1));
+        }
+    }
+
+    #[test]
+    fn drop_waits_for_unfinished_targets() {
+        let worker = worker();
+        let signaled = Arc::new(AtomicBool::new(false));
+        let future = worker.push(Target::Mock(signaled.clone()));
+
+        let signaler = signal_later(&signaled, Duration::from_millis(20));
+        drop(worker);
+        assert!(signaled.load(Ordering::Acquire));
+        assert_eq!(block_on(future), Ok(()));
+
+        signaler.join().unwrap();
+        assert_eq!(Arc::strong_count(&signaled), 1);
+    }
+
+    #[test]
+    fn drop_resolves_signaled_targets() {
+        let worker = worker();
+        let futures: Vec<_> = (0..3)
+            .map(|_| worker.push(Target::Mock(Arc::new(AtomicBool::new(true)))))
+            .collect();
+        drop(worker);
+        assert!(futures.iter().all(is_ready));
+    }
+}
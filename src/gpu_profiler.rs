@@ -0,0 +1,426 @@
+use crate::command_buffer::CommandBuffer;
+use crate::fence::Fence;
+use crate::query_pool::{QueryPool, TimestampQuery};
+use crate::queue::Queue;
+use ash::prelude::VkResult;
+use ash::vk;
+use std::cell::RefCell;
+use std::fmt::Write;
+use std::time::Duration;
+
+/// Frame-based GPU profiler. Measures named scopes of command buffers with timestamp queries.
+///
+/// Each frame in flight uses its own query pool. Results of a frame are resolved when the same
+/// pool is reused, after the frame's fence is signaled.
+pub struct GpuProfiler {
+    slots: Vec<FrameSlot>,
+    current: usize,
+    frame_index: u64,
+    recording: RefCell<Recording>,
+}
+
+impl GpuProfiler {
+    /// Creates profiler with one frame in flight per pool in `pools`.
+    ///
+    /// # Panics
+    /// * if `pools` is empty.
+    pub fn new(pools: Vec<QueryPool<TimestampQuery>>) -> Self {
+        assert!(
+            !pools.is_empty(),
+            "Profiler requires at least one query pool"
+        );
+        let slots: Vec<FrameSlot> = pools
+            .into_iter()
+            .map(|pool| FrameSlot {
+                pool,
+                fence: None,
+                frame_index: 0,
+                scopes: Vec::new(),
+            })
+            .collect();
+
+        let current = slots.len() - 1;
+        Self {
+            slots,
+            current,
+            frame_index: 0,
+            recording: RefCell::new(Recording::default()),
+        }
+    }
+
+    /// Creates profiler with `frames_in_flight` query pools, each able to measure `max_scopes`
+    /// scopes per frame, recorded to command buffers of `queue` family.
+    pub fn create(queue: &Queue, frames_in_flight: usize, max_scopes: u32) -> VkResult<Self> {
+        let pools = (0..frames_in_flight)
+            .map(|_| QueryPool::create_timestamps(queue, max_scopes * 2))
+            .collect::<VkResult<_>>()?;
+        Ok(Self::new(pools))
+    }
+
+    /// Starts new frame, which completion is signaled by `fence`. Records reset of frame queries
+    /// to `command_buffer`, so it must be submitted before other command buffers of the frame.
+    ///
+    /// Returns profile of frame, previously recorded with the same query pool. Doesn't wait:
+    /// frame is skipped if its fence isn't signaled and some of its queries are unavailable.
+    /// So `fence` may be waited and reset before the call.
+    ///
+    /// # Safety
+    /// * `command_buffer` must be in recording state.
+    /// * `command_buffer` must not be accessed by other threads during the call.
+    pub unsafe fn begin_frame(
+        &mut self,
+        command_buffer: &CommandBuffer,
+        fence: Fence,
+    ) -> VkResult<Option<FrameProfile>> {
+        self.finish_recording();
+        self.current = (self.current + 1) % self.slots.len();
+        self.frame_index += 1;
+
+        let slot = &mut self.slots[self.current];
+        let profile = slot.resolve()?;
+
+        let query_count = slot.pool.dependencies().query_count;
+        slot.pool.cmd_reset(command_buffer, 0, query_count);
+        slot.fence = Some(fence);
+        slot.frame_index = self.frame_index;
+        Ok(profile)
+    }
+
+    /// Opens scope, named `name`. Begin timestamp is written to `command_buffer` now, end
+    /// timestamp is written when scope is dropped. Scopes, opened while this one is alive, are
+    /// its children.
+    ///
+    /// If frame isn't started or all its queries are used, scope isn't measured.
+    ///
+    /// # Safety
+    /// * `command_buffer` must be in recording state until returned scope is dropped.
+    /// * `command_buffer` must not be accessed by other threads during the call and the drop.
+    pub unsafe fn scope(&self, command_buffer: &CommandBuffer, name: &str) -> ProfileScope<'_> {
+        let pool = &self.slots[self.current].pool;
+        let mut recording = self.recording.borrow_mut();
+        let first_query = recording.scopes.len() as u32 * 2;
+        let out_of_queries = first_query + 2 > pool.dependencies().query_count;
+        if self.frame_index == 0 || out_of_queries {
+            log::warn!("GPU profiler can't measure scope {}", name);
+            return ProfileScope {
+                profiler: self,
+                command_buffer: command_buffer.clone(),
+                index: None,
+            };
+        }
+
+        pool.cmd_write_timestamp(
+            command_buffer,
+            vk::PipelineStageFlags::TOP_OF_PIPE,
+            first_query,
+        );
+
+        let index = recording.scopes.len();
+        let parent = recording.open;
+        recording.scopes.push(ScopeRecord {
+            name: name.into(),
+            parent,
+            first_query,
+        });
+        recording.open = Some(index);
+
+        ProfileScope {
+            profiler: self,
+            command_buffer: command_buffer.clone(),
+            index: Some(index),
+        }
+    }
+
+    /// Moves scopes, recorded in current frame, to its slot.
+    fn finish_recording(&mut self) {
+        let recording = self.recording.get_mut();
+        recording.open = None;
+        self.slots[self.current].scopes = recording.scopes.drain(..).collect();
+    }
+
+    /// # Safety
+    /// * same as of `scope`, which opened scope at `index`.
+    unsafe fn close_scope(&self, command_buffer: &CommandBuffer, index: usize) {
+        let pool = &self.slots[self.current].pool;
+        let mut recording = self.recording.borrow_mut();
+        let scope = &recording.scopes[index];
+        let end_query = scope.first_query + 1;
+        let parent = scope.parent;
+        pool.cmd_write_timestamp(
+            command_buffer,
+            vk::PipelineStageFlags::BOTTOM_OF_PIPE,
+            end_query,
+        );
+        recording.open = parent;
+    }
+}
+
+/// Measured scope of command buffer. Writes end timestamp on drop.
+pub struct ProfileScope<'a> {
+    profiler: &'a GpuProfiler,
+    command_buffer: CommandBuffer,
+    index: Option<usize>,
+}
+
+impl Drop for ProfileScope<'_> {
+    fn drop(&mut self) {
+        if let Some(index) = self.index {
+            // Command buffer is recording until scope is dropped, as required by `scope`.
+            unsafe { self.profiler.close_scope(&self.command_buffer, index) }
+        }
+    }
+}
+
+#[derive(Default)]
+struct Recording {
+    scopes: Vec<ScopeRecord>,
+    open: Option<usize>,
+}
+
+struct ScopeRecord {
+    name: String,
+    parent: Option<usize>,
+    first_query: u32,
+}
+
+struct FrameSlot {
+    pool: QueryPool<TimestampQuery>,
+    fence: Option<Fence>,
+    frame_index: u64,
+    scopes: Vec<ScopeRecord>,
+}
+
+impl FrameSlot {
+    fn resolve(&mut self) -> VkResult<Option<FrameProfile>> {
+        let fence = match self.fence.take() {
+            Some(fence) => fence,
+            None => return Ok(None),
+        };
+
+        let scopes: Vec<ScopeRecord> = self.scopes.drain(..).collect();
+        let query_count = scopes.len() as u32 * 2;
+        let timestamps = match query_count {
+            0 => Vec::new(),
+            _ => self.pool.get_results(0, query_count)?,
+        };
+
+        // Fence may be reset already, if it was waited before `begin_frame`.
+        let all_available = timestamps.iter().all(Option::is_some);
+        if !all_available && !fence.is_signaled()? {
+            log::debug!(
+                "GPU profiler skips frame {}, which is not complete",
+                self.frame_index
+            );
+            return Ok(None);
+        }
+
+        // Scopes of command buffers, which weren't submitted, and leaked scopes have no results.
+        let mut nodes: Vec<Option<ScopeNode>> = scopes
+            .iter()
+            .map(|scope| {
+                let begin = timestamps
+                    .get(scope.first_query as usize)
+                    .copied()
+                    .flatten();
+                let end = timestamps
+                    .get(scope.first_query as usize + 1)
+                    .copied()
+                    .flatten();
+                let (begin, end) = match (begin, end) {
+                    (Some(begin), Some(end)) => (begin, end),
+                    _ => {
+                        log::debug!("GPU profiler skips unavailable scope {}", scope.name);
+                        return None;
+                    }
+                };
+                Some(ScopeNode {
+                    name: scope.name.clone(),
+                    begin,
+                    duration: end.checked_sub(begin).unwrap_or_default(),
+                    children: Vec::new(),
+                })
+            })
+            .collect();
+
+        // Children are always recorded after parents, so build tree from the end.
+        // Children of skipped scopes are attached to the closest measured ancestor.
+        let mut roots = Vec::new();
+        for (index, scope) in scopes.iter().enumerate().rev() {
+            let node = match nodes[index].take() {
+                Some(node) => node,
+                None => continue,
+            };
+            let mut parent = scope.parent;
+            while let Some(p) = parent {
+                if nodes[p].is_some() {
+                    break;
+                }
+                parent = scopes[p].parent;
+            }
+            match parent.and_then(|p| nodes[p].as_mut()) {
+                Some(parent) => parent.children.insert(0, node),
+                None => roots.insert(0, node),
+            }
+        }
+
+        Ok(Some(FrameProfile {
+            frame_index: self.frame_index,
+            roots,
+        }))
+    }
+}
+
+/// Resolved scopes of one frame.
+#[derive(Debug, Clone)]
+pub struct FrameProfile {
+    pub frame_index: u64,
+    pub roots: Vec<ScopeNode>,
+}
+
+impl FrameProfile {
+    /// Serializes profile to Chrome trace event JSON format.
+    pub fn to_chrome_trace(&self) -> String {
+        chrome_trace(std::slice::from_ref(self))
+    }
+}
+
+/// Measured scope with nested scopes.
+#[derive(Debug, Clone)]
+pub struct ScopeNode {
+    pub name: String,
+    /// GPU timestamp of scope begin, counted from unspecified moment.
+    pub begin: Duration,
+    pub duration: Duration,
+    pub children: Vec<ScopeNode>,
+}
+
+/// Serializes `frames` to Chrome trace event JSON format.
+pub fn chrome_trace(frames: &[FrameProfile]) -> String {
+    let mut events = Vec::new();
+    for frame in frames {
+        for root in &frame.roots {
+            push_trace_events(root, frame.frame_index, &mut events);
+        }
+    }
+
+    format!("{{\"traceEvents\":[{}]}}", events.join(","))
+}
+
+fn push_trace_events(node: &ScopeNode, frame_index: u64, events: &mut Vec<String>) {
+    let mut event = String::from("{\"name\":\"");
+    escape_json(&node.name, &mut event);
+    let _ = write!(
+        event,
+        "\",\"cat\":\"gpu\",\"ph\":\"X\",\"ts\":{:.3},\"dur\":{:.3},\"pid\":0,\"tid\":0,\"args\":{{\"frame\":{}}}}}",
+        node.begin.as_secs_f64() * 1e6,
+        node.duration.as_secs_f64() * 1e6,
+        frame_index
+    );
+    events.push(event);
+
+    for child in &node.children {
+        push_trace_events(child, frame_index, events);
+    }
+}
+
+fn escape_json(s: &str, out: &mut String) {
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node(name: &str, begin_us: u64, duration_us: u64, children: Vec<ScopeNode>) -> ScopeNode {
+        ScopeNode {
+            name: name.into(),
+            begin: Duration::from_micros(begin_us),
+            duration: Duration::from_micros(duration_us),
+            children,
+        }
+    }
+
+    fn escaped(s: &str) -> String {
+        let mut out = String::new();
+        escape_json(s, &mut out);
+        out
+    }
+
+    #[test]
+    fn escape_json_keeps_plain_text() {
+        assert_eq!(escaped("shadow pass 1"), "shadow pass 1");
+        assert_eq!(escaped("проход"), "проход");
+    }
+
+    #[test]
+    fn escape_json_escapes_special_chars() {
+        assert_eq!(escaped("a\"b"), "a\\\"b");
+        assert_eq!(escaped("a\\b"), "a\\\\b");
+        assert_eq!(escaped("a\nb\rc\td"), "a\\nb\\rc\\td");
+        assert_eq!(escaped("\u{1}\u{1f}"), "\\u0001\\u001f");
+    }
+
+    #[test]
+    fn chrome_trace_of_no_frames_is_empty() {
+        assert_eq!(chrome_trace(&[]), "{\"traceEvents\":[]}");
+    }
+
+    #[test]
+    fn chrome_trace_lists_nested_scopes_in_order() {
+        let frame = FrameProfile {
+            frame_index: 7,
+            roots: vec![
+                node("frame", 10, 100, vec![node("shadow", 20, 30, Vec::new())]),
+                node("ui \"overlay\"", 200, 5, Vec::new()),
+            ],
+        };
+
+        let expected = concat!(
+            "{\"traceEvents\":[",
+            "{\"name\":\"frame\",\"cat\":\"gpu\",\"ph\":\"X\",\"ts\":10.000,\"dur\":100.000,",
+            "\"pid\":0,\"tid\":0,\"args\":{\"frame\":7}},",
+            "{\"name\":\"shadow\",\"cat\":\"gpu\",\"ph\":\"X\",\"ts\":20.000,\"dur\":30.000,",
+            "\"pid\":0,\"tid\":0,\"args\":{\"frame\":7}},",
+            "{\"name\":\"ui \\\"overlay\\\"\",\"cat\":\"gpu\",\"ph\":\"X\",\"ts\":200.000,",
+            "\"dur\":5.000,\"pid\":0,\"tid\":0,\"args\":{\"frame\":7}}",
+            "]}"
+        );
+        assert_eq!(frame.to_chrome_trace(), expected);
+    }
+
+    #[test]
+    fn chrome_trace_merges_frames() {
+        let frames = [
+            FrameProfile {
+                frame_index: 1,
+                roots: vec![node("a", 0, 1, Vec::new())],
+            },
+            FrameProfile {
+                frame_index: 2,
+                roots: vec![node("b", 0, 1, Vec::new())],
+            },
+        ];
+
+        let trace = chrome_trace(&frames);
+        let first = trace
+            .find("\"frame\":1")
+            .expect("First frame is serialized");
+        let second = trace
+            .find("\"frame\":2")
+            .expect("Second frame is serialized");
+        assert!(first < second);
+        assert_eq!(trace.matches("\"ph\":\"X\"").count(), 2);
+    }
+}
@@ -0,0 +1,153 @@
+use crate::device::Device;
+use crate::swapchain::Swapchain;
+use crate::{Handle, RawHandle, Sharing};
+use ash::prelude::VkResult;
+use ash::version::DeviceV1_0;
+use ash::vk;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct ImageDesc {
+    pub flags: vk::ImageCreateFlags,
+    pub image_type: vk::ImageType,
+    pub format: vk::Format,
+    pub extent: vk::Extent3D,
+    pub mip_levels: u32,
+    pub array_layers: u32,
+    pub samples: vk::SampleCountFlags,
+    pub tiling: vk::ImageTiling,
+    pub usage: vk::ImageUsageFlags,
+    pub sharing: Sharing,
+    pub initial_layout: vk::ImageLayout,
+}
+
+impl ImageDesc {
+    pub fn create_info(&self) -> vk::ImageCreateInfoBuilder<'_> {
+        vk::ImageCreateInfo::builder()
+            .flags(self.flags)
+            .image_type(self.image_type)
+            .format(self.format)
+            .extent(self.extent)
+            .mip_levels(self.mip_levels)
+            .array_layers(self.array_layers)
+            .samples(self.samples)
+            .tiling(self.tiling)
+            .usage(self.usage)
+            .sharing_mode(self.sharing.mode())
+            .queue_family_indices(self.sharing.queue_family_indices())
+            .initial_layout(self.initial_layout)
+    }
+}
+
+pub struct Deps {
+    pub device: Device,
+    pub desc: ImageDesc,
+}
+
+impl RawHandle for vk::Image {
+    type Dependencies = Deps;
+
+    fn name() -> &'static str {
+        "image"
+    }
+
+    fn device(deps: &Self::Dependencies) -> Option<&Device> {
+        Some(&deps.device)
+    }
+
+    fn object_type() -> vk::ObjectType {
+        vk::ObjectType::IMAGE
+    }
+
+    fn raw_handles(&self) -> Vec<u64> {
+        vec![vk::Handle::as_raw(*self)]
+    }
+
+    fn destroy(&self, dependencies: &Self::Dependencies) {
+        unsafe { dependencies.device.destroy_image(*self, None) }
+    }
+}
+
+pub type Image = Handle<vk::Image, Deps>;
+
+impl Image {
+    pub fn create(device: &Device, desc: &ImageDesc) -> VkResult<Self> {
+        unsafe {
+            let raw = device.create_image(&desc.create_info(), None)?;
+            let deps = Deps {
+                device: device.clone(),
+                desc: desc.clone(),
+            };
+            Ok(Self::new(raw, deps))
+        }
+    }
+
+    pub fn desc(&self) -> &ImageDesc {
+        &self.dependencies().desc
+    }
+}
+
+/// Image owned by swapchain. It is released together with swapchain, so it is never destroyed here.
+pub struct RawSwapchainImage {
+    handle: vk::Image,
+    index: u32,
+}
+
+impl RawSwapchainImage {
+    pub fn new(handle: vk::Image, index: u32) -> Self {
+        Self { handle, index }
+    }
+
+    pub fn handle(&self) -> &vk::Image {
+        &self.handle
+    }
+
+    /// Index of image in swapchain images array.
+    pub fn index(&self) -> u32 {
+        self.index
+    }
+}
+
+pub struct SwapchainImageDeps {
+    pub swapchain: Swapchain,
+}
+
+impl RawHandle for RawSwapchainImage {
+    type Dependencies = SwapchainImageDeps;
+
+    fn name() -> &'static str {
+        "swapchain image"
+    }
+
+    fn device(deps: &Self::Dependencies) -> Option<&Device> {
+        Some(&deps.swapchain.dependencies().device)
+    }
+
+    fn object_type() -> vk::ObjectType {
+        vk::ObjectType::IMAGE
+    }
+
+    fn raw_handles(&self) -> Vec<u64> {
+        vec![vk::Handle::as_raw(self.handle)]
+    }
+
+    fn destroy(&self, _: &Self::Dependencies) {}
+}
+
+pub type SwapchainImage = Handle<RawSwapchainImage, SwapchainImageDeps>;
+
+/// Returns images of `swapchain`. Each of them keeps `swapchain` alive.
+pub fn swapchain_images(swapchain: &Swapchain) -> VkResult<Vec<SwapchainImage>> {
+    let loader = &swapchain.dependencies().loader;
+    let raw_images = unsafe { loader.get_swapchain_images(**swapchain)? };
+    let images = raw_images
+        .into_iter()
+        .enumerate()
+        .map(|(index, raw)| unsafe {
+            let deps = SwapchainImageDeps {
+                swapchain: swapchain.clone(),
+            };
+            SwapchainImage::new(RawSwapchainImage::new(raw, index as u32), deps)
+        })
+        .collect();
+    Ok(images)
+}
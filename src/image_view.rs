@@ -0,0 +1,131 @@
+use crate::device::Device;
+use crate::image::{Image, SwapchainImage};
+use crate::{Handle, RawHandle};
+use ash::prelude::VkResult;
+use ash::version::DeviceV1_0;
+use ash::vk;
+use std::hash::Hash;
+
+/// Image, viewed by `ImageView`.
+pub enum ViewedImage {
+    Image(Image),
+    Swapchain(SwapchainImage),
+}
+
+impl ViewedImage {
+    pub fn raw(&self) -> vk::Image {
+        match self {
+            ViewedImage::Image(image) => **image,
+            ViewedImage::Swapchain(image) => *image.handle().handle(),
+        }
+    }
+
+    pub fn device(&self) -> &Device {
+        match self {
+            ViewedImage::Image(image) => &image.dependencies().device,
+            ViewedImage::Swapchain(image) => &image.dependencies().swapchain.dependencies().device,
+        }
+    }
+}
+
+impl From<Image> for ViewedImage {
+    fn from(image: Image) -> Self {
+        ViewedImage::Image(image)
+    }
+}
+
+impl From<SwapchainImage> for ViewedImage {
+    fn from(image: SwapchainImage) -> Self {
+        ViewedImage::Swapchain(image)
+    }
+}
+
+#[derive(Debug, Copy, Clone, Default)]
+pub struct ImageViewDesc {
+    pub flags: vk::ImageViewCreateFlags,
+    pub view_type: vk::ImageViewType,
+    pub format: vk::Format,
+    pub components: vk::ComponentMapping,
+    pub subresource_range: vk::ImageSubresourceRange,
+}
+
+impl ImageViewDesc {
+    pub fn create_info(&self, image: vk::Image) -> vk::ImageViewCreateInfoBuilder<'static> {
+        vk::ImageViewCreateInfo::builder()
+            .flags(self.flags)
+            .image(image)
+            .view_type(self.view_type)
+            .format(self.format)
+            .components(self.components)
+            .subresource_range(self.subresource_range)
+    }
+
+    fn key(&self) -> impl Eq + Hash {
+        let c = self.components;
+        let r = self.subresource_range;
+        (
+            self.flags,
+            self.view_type,
+            self.format,
+            (c.r, c.g, c.b, c.a),
+            (
+                r.aspect_mask,
+                r.base_mip_level,
+                r.level_count,
+                r.base_array_layer,
+                r.layer_count,
+            ),
+        )
+    }
+}
+
+impl_eq_hash_by_key!(ImageViewDesc);
+
+pub struct Deps {
+    pub image: ViewedImage,
+    pub desc: ImageViewDesc,
+}
+
+impl RawHandle for vk::ImageView {
+    type Dependencies = Deps;
+
+    fn name() -> &'static str {
+        "image view"
+    }
+
+    fn device(deps: &Self::Dependencies) -> Option<&Device> {
+        Some(deps.image.device())
+    }
+
+    fn object_type() -> vk::ObjectType {
+        vk::ObjectType::IMAGE_VIEW
+    }
+
+    fn raw_handles(&self) -> Vec<u64> {
+        vec![vk::Handle::as_raw(*self)]
+    }
+
+    fn destroy(&self, deps: &Self::Dependencies) {
+        let device = deps.image.device();
+        unsafe { device.destroy_image_view(*self, None) }
+    }
+}
+
+pub type ImageView = Handle<vk::ImageView, Deps>;
+
+impl ImageView {
+    pub fn create(image: impl Into<ViewedImage>, desc: &ImageViewDesc) -> VkResult<Self> {
+        let image = image.into();
+        unsafe {
+            let raw = image
+                .device()
+                .create_image_view(&desc.create_info(image.raw()), None)?;
+            let deps = Deps { image, desc: *desc };
+            Ok(Self::new(raw, deps))
+        }
+    }
+
+    pub fn desc(&self) -> &ImageViewDesc {
+        &self.dependencies().desc
+    }
+}
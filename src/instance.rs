@@ -1,8 +1,35 @@
+use crate::debug_report::{debug_report_with_default_callback, Callback};
 use crate::{Handle, RawHandle};
-use ash::version::InstanceV1_0;
+use ash::extensions::ext;
+use ash::prelude::VkResult;
+use ash::version::{EntryV1_0, InstanceV1_0};
+use ash::{vk, InstanceError};
+use std::error::Error;
+use std::ffi::{CStr, CString};
+use std::fmt;
+use std::os::raw::c_char;
+use std::ptr;
+use std::slice;
 
 pub struct Deps {
     pub entry: ash::Entry,
+    /// Loaded if `VK_EXT_debug_utils` is enabled. Used to name handles.
+    pub debug_utils: Option<ext::DebugUtils>,
+    /// Receives messages, emitted during instance creation and destruction.
+    pub creation_callback: Option<Box<Callback>>,
+    pub api_version: u32,
+    pub enabled_layers: Vec<CString>,
+    pub enabled_extensions: Vec<CString>,
+}
+
+impl Deps {
+    pub fn is_layer_enabled(&self, name: &CStr) -> bool {
+        self.enabled_layers.iter().any(|l| l.as_c_str() == name)
+    }
+
+    pub fn is_extension_enabled(&self, name: &CStr) -> bool {
+        self.enabled_extensions.iter().any(|e| e.as_c_str() == name)
+    }
 }
 
 impl RawHandle for ash::Instance {
@@ -12,9 +39,404 @@ impl RawHandle for ash::Instance {
         "instance"
     }
 
+    fn object_type() -> vk::ObjectType {
+        vk::ObjectType::INSTANCE
+    }
+
+    fn raw_handles(&self) -> Vec<u64> {
+        vec![vk::Handle::as_raw(self.handle())]
+    }
+
     fn destroy(&self, _: &Self::Dependencies) {
         unsafe { self.destroy_instance(None) }
     }
 }
 
 pub type Instance = Handle<ash::Instance, Deps>;
+
+/// Debug messenger, chained into `vk::InstanceCreateInfo` to receive messages of
+/// `vkCreateInstance` and `vkDestroyInstance`.
+pub struct CreationMessenger {
+    pub severity: vk::DebugUtilsMessageSeverityFlagsEXT,
+    pub types: vk::DebugUtilsMessageTypeFlagsEXT,
+    pub callback: Callback,
+}
+
+impl Instance {
+    /// Creates instance. If `messenger` is provided, it is chained into `create_info`
+    /// and its callback is stored in `Deps` until instance is destroyed.
+    /// Fails with `vk::Result::ERROR_EXTENSION_NOT_PRESENT` if `messenger` is provided,
+    /// but `VK_EXT_debug_utils` is not enabled by `create_info`.
+    ///
+    /// # Safety
+    /// * `create_info` must be valid instance create info.
+    pub unsafe fn create(
+        entry: ash::Entry,
+        create_info: &vk::InstanceCreateInfo,
+        messenger: Option<CreationMessenger>,
+    ) -> Result<Self, InstanceError> {
+        let mut create_info = *create_info;
+        let messenger_ci: vk::DebugUtilsMessengerCreateInfoEXT;
+        let mut creation_callback = None;
+
+        if let Some(messenger) = messenger {
+            let extensions = raw_names(
+                create_info.pp_enabled_extension_names,
+                create_info.enabled_extension_count,
+            );
+            if !extensions.contains(&ext::DebugUtils::name()) {
+                return Err(InstanceError::VkError(
+                    vk::Result::ERROR_EXTENSION_NOT_PRESENT,
+                ));
+            }
+
+            let mut callback = Box::new(messenger.callback);
+            let callback_ptr: *mut Callback = callback.as_mut();
+            let mut ci = vk::DebugUtilsMessengerCreateInfoEXT::builder()
+                .message_severity(messenger.severity)
+                .message_type(messenger.types)
+                .user_data(callback_ptr.cast())
+                .pfn_user_callback(Some(debug_report_with_default_callback))
+                .build();
+            ci.p_next = create_info.p_next;
+            messenger_ci = ci;
+            create_info.p_next =
+                (&messenger_ci as *const vk::DebugUtilsMessengerCreateInfoEXT).cast();
+            creation_callback = Some(callback);
+        }
+
+        let raw = entry.create_instance(&create_info, None)?;
+
+        let enabled_layers = owned_names(
+            create_info.pp_enabled_layer_names,
+            create_info.enabled_layer_count,
+        );
+        let enabled_extensions = owned_names(
+            create_info.pp_enabled_extension_names,
+            create_info.enabled_extension_count,
+        );
+        let api_version = match create_info.p_application_info.as_ref() {
+            Some(app_info) if app_info.api_version != 0 => app_info.api_version,
+            _ => vk::API_VERSION_1_0,
+        };
+
+        let debug_utils_name = ext::DebugUtils::name();
+        let debug_utils = match enabled_extensions
+            .iter()
+            .any(|e| e.as_c_str() == debug_utils_name)
+        {
+            true => Some(ext::DebugUtils::new(&entry, &raw)),
+            false => None,
+        };
+
+        let deps = Deps {
+            entry,
+            debug_utils,
+            creation_callback,
+            api_version,
+            enabled_layers,
+            enabled_extensions,
+        };
+        Ok(Self::new(raw, deps))
+    }
+}
+
+unsafe fn owned_names(names: *const *const c_char, count: u32) -> Vec<CString> {
+    raw_names(names, count)
+        .into_iter()
+        .map(CStr::to_owned)
+        .collect()
+}
+
+unsafe fn raw_names<'a>(names: *const *const c_char, count: u32) -> Vec<&'a CStr> {
+    if names.is_null() {
+        return Vec::new();
+    }
+
+    slice::from_raw_parts(names, count as usize)
+        .iter()
+        .map(|&name| CStr::from_ptr(name))
+        .collect()
+}
+
+/// Builder of `Instance`, checking requested layers and extensions against available ones.
+///
+/// Missing optional layers and extensions are skipped; missing required ones fail the build.
+/// Enabled ones are recorded in `Deps`.
+pub struct InstanceBuilder {
+    application_name: CString,
+    application_version: u32,
+    engine_name: CString,
+    engine_version: u32,
+    min_api_version: u32,
+    max_api_version: u32,
+    required_layers: Vec<CString>,
+    optional_layers: Vec<CString>,
+    required_extensions: Vec<CString>,
+    optional_extensions: Vec<CString>,
+    messenger: Option<CreationMessenger>,
+}
+
+impl Default for InstanceBuilder {
+    fn default() -> Self {
+        Self {
+            application_name: CString::default(),
+            application_version: 0,
+            engine_name: CString::default(),
+            engine_version: 0,
+            min_api_version: vk::API_VERSION_1_0,
+            max_api_version: vk::API_VERSION_1_0,
+            required_layers: Vec::new(),
+            optional_layers: Vec::new(),
+            required_extensions: Vec::new(),
+            optional_extensions: Vec::new(),
+            messenger: None,
+        }
+    }
+}
+
+impl InstanceBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn application(mut self, name: &str, version: u32) -> Self {
+        self.application_name = to_cstring(name);
+        self.application_version = version;
+        self
+    }
+
+    pub fn engine(mut self, name: &str, version: u32) -> Self {
+        self.engine_name = to_cstring(name);
+        self.engine_version = version;
+        self
+    }
+
+    /// Highest supported version in `min..=max` is used. Build fails if `min` is not supported.
+    pub fn api_version(mut self, min: u32, max: u32) -> Self {
+        self.min_api_version = min;
+        self.max_api_version = max;
+        self
+    }
+
+    pub fn require_layer(mut self, name: &CStr) -> Self {
+        self.required_layers.push(name.to_owned());
+        self
+    }
+
+    pub fn optional_layer(mut self, name: &CStr) -> Self {
+        self.optional_layers.push(name.to_owned());
+        self
+    }
+
+    pub fn require_extension(mut self, name: &CStr) -> Self {
+        self.required_extensions.push(name.to_owned());
+        self
+    }
+
+    pub fn require_extensions(mut self, names: &[&CStr]) -> Self {
+        let names = names.iter().map(|&n| n.to_owned());
+        self.required_extensions.extend(names);
+        self
+    }
+
+    pub fn optional_extension(mut self, name: &CStr) -> Self {
+        self.optional_extensions.push(name.to_owned());
+        self
+    }
+
+    /// Messenger, receiving messages of instance creation and destruction.
+    /// Makes `VK_EXT_debug_utils` required.
+    pub fn creation_messenger(mut self, messenger: CreationMessenger) -> Self {
+        self.messenger = Some(messenger);
+        self.require_extension(ext::DebugUtils::name())
+    }
+
+    pub fn build(self, entry: ash::Entry) -> Result<Instance, BuildError> {
+        let supported_version = entry
+            .try_enumerate_instance_version()?
+            .unwrap_or(vk::API_VERSION_1_0);
+        if supported_version < self.min_api_version {
+            return Err(BuildError::UnsupportedApiVersion {
+                required: self.min_api_version,
+                supported: supported_version,
+            });
+        }
+        let api_version = supported_version.min(self.max_api_version);
+
+        let available_layers: Vec<CString> = entry
+            .enumerate_instance_layer_properties()?
+            .iter()
+            .map(|l| unsafe { CStr::from_ptr(l.layer_name.as_ptr()) }.to_owned())
+            .collect();
+
+        // Extensions, provided by enabled layers, are available too.
+        let layers = enabled(
+            &self.required_layers,
+            &self.optional_layers,
+            &available_layers,
+        );
+        let mut available_extensions = extension_names(&entry, None)?;
+        for layer in layers.iter().filter(|l| available_layers.contains(l)) {
+            available_extensions.extend(extension_names(&entry, Some(layer))?);
+        }
+
+        let missing_layers = missing(&self.required_layers, &available_layers);
+        let missing_extensions = missing(&self.required_extensions, &available_extensions);
+        if !missing_layers.is_empty() || !missing_extensions.is_empty() {
+            return Err(BuildError::Missing {
+                layers: missing_layers,
+                extensions: missing_extensions,
+            });
+        }
+
+        let extensions = enabled(
+            &self.required_extensions,
+            &self.optional_extensions,
+            &available_extensions,
+        );
+        let layer_ptrs: Vec<_> = layers.iter().map(|l| l.as_ptr()).collect();
+        let extension_ptrs: Vec<_> = extensions.iter().map(|e| e.as_ptr()).collect();
+
+        let app_info = vk::ApplicationInfo::builder()
+            .application_name(&self.application_name)
+            .application_version(self.application_version)
+            .engine_name(&self.engine_name)
+            .engine_version(self.engine_version)
+            .api_version(api_version);
+
+        let ci = vk::InstanceCreateInfo::builder()
+            .application_info(&app_info)
+            .enabled_layer_names(&layer_ptrs)
+            .enabled_extension_names(&extension_ptrs);
+
+        unsafe { Instance::create(entry, &ci, self.messenger) }.map_err(BuildError::Create)
+    }
+}
+
+/// Instance extensions of Vulkan implementation or of `layer`.
+fn extension_names(entry: &ash::Entry, layer: Option<&CStr>) -> VkResult<Vec<CString>> {
+    let fp = entry.fp_v1_0();
+    let layer = layer.map_or(ptr::null(), CStr::as_ptr);
+    let properties = unsafe {
+        loop {
+            let mut count = 0;
+            fp.enumerate_instance_extension_properties(layer, &mut count, ptr::null_mut())
+                .result()?;
+            let mut properties = Vec::with_capacity(count as usize);
+            let result = fp.enumerate_instance_extension_properties(
+                layer,
+                &mut count,
+                properties.as_mut_ptr(),
+            );
+            // New extensions may appear between calls.
+            if result == vk::Result::INCOMPLETE {
+                continue;
+            }
+            result.result()?;
+            properties.set_len(count as usize);
+            break properties;
+        }
+    };
+
+    let names = properties
+        .iter()
+        .map(|e: &vk::ExtensionProperties| unsafe { CStr::from_ptr(e.extension_name.as_ptr()) })
+        .map(CStr::to_owned)
+        .collect();
+    Ok(names)
+}
+
+fn to_cstring(s: &str) -> CString {
+    let s = s.split('\0').next().unwrap_or_default();
+    CString::new(s).expect("Nul bytes are removed")
+}
+
+fn missing(required: &[CString], available: &[CString]) -> Vec<CString> {
+    let mut missing: Vec<CString> = Vec::new();
+    for name in required {
+        if !available.contains(name) && !missing.contains(name) {
+            missing.push(name.clone());
+        }
+    }
+    missing
+}
+
+fn enabled(required: &[CString], optional: &[CString], available: &[CString]) -> Vec<CString> {
+    let mut enabled: Vec<CString> = Vec::new();
+    let optional = optional.iter().filter(|name| available.contains(name));
+    for name in required.iter().chain(optional) {
+        if !enabled.contains(name) {
+            enabled.push(name.clone());
+        }
+    }
+    enabled
+}
+
+#[derive(Debug)]
+pub enum BuildError {
+    /// Required layers and extensions, not supported by Vulkan implementation.
+    Missing {
+        layers: Vec<CString>,
+        extensions: Vec<CString>,
+    },
+    UnsupportedApiVersion {
+        required: u32,
+        supported: u32,
+    },
+    Vulkan(vk::Result),
+    Create(InstanceError),
+}
+
+impl From<vk::Result> for BuildError {
+    fn from(e: vk::Result) -> Self {
+        Self::Vulkan(e)
+    }
+}
+
+impl Error for BuildError {}
+
+impl fmt::Display for BuildError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BuildError::Missing { layers, extensions } => {
+                write!(f, "Missing required")?;
+                if !layers.is_empty() {
+                    write!(f, " layers: {}", join_names(layers))?;
+                }
+                if !extensions.is_empty() {
+                    if !layers.is_empty() {
+                        write!(f, ";")?;
+                    }
+                    write!(f, " extensions: {}", join_names(extensions))?;
+                }
+                Ok(())
+            }
+            BuildError::UnsupportedApiVersion {
+                required,
+                supported,
+            } => write!(
+                f,
+                "Required Vulkan {} is not supported, highest supported is {}",
+                version_string(*required),
+                version_string(*supported)
+            ),
+            BuildError::Vulkan(e) => write!(f, "Can't enumerate instance properties: {}", e),
+            BuildError::Create(e) => write!(f, "Can't create instance: {}", e),
+        }
+    }
+}
+
+fn join_names(names: &[CString]) -> String {
+    let names: Vec<_> = names.iter().map(|n| n.to_string_lossy()).collect();
+    names.join(", ")
+}
+
+pub(crate) fn version_string(version: u32) -> String {
+    format!(
+        "{}.{}.{}",
+        vk::version_major(version),
+        vk::version_minor(version),
+        vk::version_patch(version)
+    )
+}
@@ -1,36 +1,130 @@
+/// Implements `PartialEq`, `Eq` and `Hash` by comparing and hashing `$ty::key()`.
+/// Used for descriptions with ash structs, which don't implement these traits.
+macro_rules! impl_eq_hash_by_key {
+    ($ty:ty) => {
+        impl PartialEq for $ty {
+            fn eq(&self, other: &Self) -> bool {
+                self.key() == other.key()
+            }
+        }
+
+        impl Eq for $ty {}
+
+        impl std::hash::Hash for $ty {
+            fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+                self.key().hash(state)
+            }
+        }
+    };
+}
+
 pub mod buffer;
+pub mod buffer_view;
 pub mod command_buffer;
 pub mod command_pool;
+pub mod debug_capture;
+pub mod debug_filter;
 pub mod debug_report;
+pub mod deletion_queue;
 pub mod descr_pool;
 pub mod descr_set;
 pub mod device;
 pub mod ds_layout;
+pub mod event;
+pub mod features;
 pub mod fence;
+pub mod framebuffer;
+#[cfg(feature = "async")]
+pub mod future;
+pub mod gpu_profiler;
+pub mod image;
+pub mod image_view;
 pub mod instance;
 pub mod memory;
+pub mod naming;
+pub mod physical_device;
 pub mod pipeline;
 pub mod pipeline_cache;
 pub mod pipeline_layout;
+pub mod query_pool;
 pub mod queue;
+pub mod registry;
 pub mod render_pass;
 pub mod sampler;
+pub mod semaphore;
 pub mod shader_module;
-pub mod swapchain;
 pub mod surface;
+pub mod swapchain;
 use std::cmp::Ordering;
 use std::fmt;
 use std::hash::{Hash, Hasher};
 use std::ops::Deref;
-use std::sync::Arc;
+use std::sync::{Arc, Weak};
 
 pub use ash;
+use ash::prelude::VkResult;
+use ash::vk;
+use deletion_queue::DeletionQueue;
+use device::Device;
+use registry::Owner;
+
+/// Outcome of waiting for synchronization primitive.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum WaitResult {
+    Signaled,
+    Timeout,
+}
+
+/// Sharing of resource between queue families.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub enum Sharing {
+    #[default]
+    Exclusive,
+    /// Resource is accessed concurrently by listed queue families.
+    Concurrent(Vec<u32>),
+}
+
+impl Sharing {
+    pub fn mode(&self) -> vk::SharingMode {
+        match self {
+            Sharing::Exclusive => vk::SharingMode::EXCLUSIVE,
+            Sharing::Concurrent(_) => vk::SharingMode::CONCURRENT,
+        }
+    }
+
+    pub fn queue_family_indices(&self) -> &[u32] {
+        match self {
+            Sharing::Exclusive => &[],
+            Sharing::Concurrent(indices) => indices,
+        }
+    }
+}
 
 pub trait RawHandle {
     type Dependencies;
 
     fn name() -> &'static str;
 
+    /// Device, handle is created from. `None` for instance-level handles.
+    fn device(_deps: &Self::Dependencies) -> Option<&Device> {
+        None
+    }
+
+    /// Device or instance, handle is created from. Used by `registry`.
+    fn owner(deps: &Self::Dependencies) -> Option<Owner> {
+        Self::device(deps).map(Owner::of_device)
+    }
+
+    /// Type of Vulkan objects, wrapped by handle. Used for debug naming.
+    fn object_type() -> vk::ObjectType {
+        vk::ObjectType::UNKNOWN
+    }
+
+    /// Raw values of Vulkan objects, wrapped by handle. Used for debug naming.
+    fn raw_handles(&self) -> Vec<u64> {
+        Vec::new()
+    }
+
     fn destroy(&self, deps: &Self::Dependencies);
 }
 
@@ -39,11 +133,21 @@ struct UniqueData<T, D> {
     dependencies: D,
 }
 
+struct Deferred<T, D>
+where
+    T: RawHandle<Dependencies = D>,
+{
+    queue: Weak<deletion_queue::Shared>,
+    retire: fn(UniqueHandle<T, D>, &deletion_queue::Shared),
+}
+
 pub struct UniqueHandle<T, D>
 where
     T: RawHandle<Dependencies = D>,
 {
     data: Option<UniqueData<T, D>>,
+    deferred: Option<Deferred<T, D>>,
+    registry_id: Option<u64>,
 }
 
 impl<T: fmt::Debug, D> fmt::Debug for UniqueHandle<T, D>
@@ -101,15 +205,32 @@ where
     /// * `handle` must be valud initialized handle;
     /// * `dependencies` must contain valid and initialized handles;
     pub unsafe fn new(handle: T, dependencies: D) -> Self {
-        log::trace!("Unique {} initialized", T::name());
+        trace_lifecycle("initialized", &handle, &dependencies);
+        let registry_id = registry::register(T::name(), || T::owner(&dependencies));
+        naming::name_created(&handle, &dependencies);
         Self {
             data: Some(UniqueData {
                 handle,
                 dependencies,
             }),
+            deferred: None,
+            registry_id,
         }
     }
 
+    /// Makes handle to be moved into `queue` on drop, instead of immediate destruction.
+    /// If `queue` is dropped already, handle is destroyed immediately.
+    pub fn defer_to(&mut self, queue: &DeletionQueue)
+    where
+        T: Send + 'static,
+        D: Send + 'static,
+    {
+        self.deferred = Some(Deferred {
+            queue: Arc::downgrade(queue.shared()),
+            retire: deletion_queue::retire,
+        });
+    }
+
     pub fn handle(&self) -> &T {
         if let Some(d) = &self.data {
             return &d.handle;
@@ -125,7 +246,10 @@ where
     }
 
     pub fn into_inner(mut self) -> (T, D) {
-        log::trace!("Unique {} unwrapped", T::name());
+        trace_lifecycle("unwrapped", self.handle(), self.dependencies());
+        if let Some(id) = self.registry_id.take() {
+            registry::unregister(id);
+        }
         let d = self
             .data
             .take()
@@ -139,10 +263,60 @@ where
     T: RawHandle<Dependencies = D>,
 {
     fn drop(&mut self) {
+        let deferred = self.deferred.take();
+        let queue = deferred.as_ref().and_then(|d| d.queue.upgrade());
+        if let (Some(deferred), Some(queue)) = (deferred, queue) {
+            if let Some(d) = &self.data {
+                trace_lifecycle("deferred", &d.handle, &d.dependencies);
+                let unique = Self {
+                    data: self.data.take(),
+                    deferred: None,
+                    registry_id: self.registry_id.take(),
+                };
+                (deferred.retire)(unique, &queue);
+            }
+            return;
+        }
+
         if let Some(d) = &self.data {
-            log::trace!("Unique {} destroyed", T::name());
+            trace_lifecycle("destroyed", &d.handle, &d.dependencies);
             d.handle.destroy(&d.dependencies)
         }
+
+        if let Some(id) = self.registry_id.take() {
+            registry::unregister(id);
+        }
+    }
+}
+
+/// Formats owner only when event is recorded.
+#[cfg(feature = "tracing")]
+struct DisplayOwner<'a>(Option<&'a Owner>);
+
+#[cfg(feature = "tracing")]
+impl fmt::Display for DisplayOwner<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.0 {
+            Some(owner) => fmt::Display::fmt(owner, f),
+            None => write!(f, "none"),
+        }
+    }
+}
+
+#[cfg_attr(not(feature = "tracing"), allow(unused_variables))]
+fn trace_lifecycle<T: RawHandle>(event: &str, handle: &T, deps: &T::Dependencies) {
+    log::trace!("Unique {} {}", T::name(), event);
+
+    #[cfg(feature = "tracing")]
+    {
+        tracing::trace!(
+            handle_type = T::name(),
+            raw_handles = ?handle.raw_handles(),
+            owner = %DisplayOwner(T::owner(deps).as_ref()),
+            "Unique {} {}",
+            T::name(),
+            event
+        );
     }
 }
 
@@ -153,7 +327,6 @@ where
     handle: Arc<UniqueHandle<T, D>>,
 }
 
-
 impl<T: fmt::Debug, D> fmt::Debug for Handle<T, D>
 where
     T: RawHandle<Dependencies = D>,
@@ -223,6 +396,44 @@ where
         self.handle.dependencies()
     }
 
+    /// Sets debug name of wrapped Vulkan objects via `VK_EXT_debug_utils`.
+    /// Does nothing for instance-level handles and if debug utils are not loaded by `Instance`.
+    pub fn set_name(&self, name: &str) -> VkResult<()> {
+        naming::set_name(self.handle(), self.dependencies(), name)
+    }
+
+    /// Same as `new`, but dropped handle is moved into `queue` instead of immediate destruction.
+    ///
+    /// # Safety
+    /// * `handle` must be valud initialized handle;
+    /// * `dependencies` must contain valid and initialized handles;
+    pub unsafe fn new_deferred(handle: T, dependencies: D, queue: &DeletionQueue) -> Self
+    where
+        T: Send + 'static,
+        D: Send + 'static,
+    {
+        let mut unique = UniqueHandle::new(handle, dependencies);
+        unique.defer_to(queue);
+        Self {
+            handle: Arc::new(unique),
+        }
+    }
+
+    /// Makes handle to be moved into `queue` on drop. Fails if handle has other references.
+    pub fn try_defer(mut self, queue: &DeletionQueue) -> Result<Self, Self>
+    where
+        T: Send + 'static,
+        D: Send + 'static,
+    {
+        match Arc::get_mut(&mut self.handle) {
+            Some(unique) => {
+                unique.defer_to(queue);
+                Ok(self)
+            }
+            None => Err(self),
+        }
+    }
+
     pub fn try_unwrap(self) -> Result<(T, D), Self> {
         match Arc::try_unwrap(self.handle) {
             Ok(unique) => Ok(unique.into_inner()),
@@ -256,4 +467,4 @@ where
     fn deref(&self) -> &Self::Target {
         self.handle()
     }
-}
\ No newline at end of file
+}
@@ -1,10 +1,26 @@
 use crate::device::Device;
 use crate::{Handle, RawHandle};
+use ash::prelude::VkResult;
 use ash::version::DeviceV1_0;
 use ash::vk;
 
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash)]
+pub struct MemoryDesc {
+    pub size: vk::DeviceSize,
+    pub memory_type_index: u32,
+}
+
+impl MemoryDesc {
+    pub fn allocate_info(&self) -> vk::MemoryAllocateInfoBuilder<'static> {
+        vk::MemoryAllocateInfo::builder()
+            .allocation_size(self.size)
+            .memory_type_index(self.memory_type_index)
+    }
+}
+
 pub struct Deps {
     pub device: Device,
+    pub desc: MemoryDesc,
 }
 
 impl RawHandle for vk::DeviceMemory {
@@ -14,9 +30,38 @@ impl RawHandle for vk::DeviceMemory {
         "memory"
     }
 
+    fn device(deps: &Self::Dependencies) -> Option<&Device> {
+        Some(&deps.device)
+    }
+
+    fn object_type() -> vk::ObjectType {
+        vk::ObjectType::DEVICE_MEMORY
+    }
+
+    fn raw_handles(&self) -> Vec<u64> {
+        vec![vk::Handle::as_raw(*self)]
+    }
+
     fn destroy(&self, deps: &Self::Dependencies) {
         unsafe { deps.device.free_memory(*self, None) }
     }
 }
 
 pub type Memory = Handle<vk::DeviceMemory, Deps>;
+
+impl Memory {
+    pub fn allocate(device: &Device, desc: &MemoryDesc) -> VkResult<Self> {
+        unsafe {
+            let raw = device.allocate_memory(&desc.allocate_info(), None)?;
+            let deps = Deps {
+                device: device.clone(),
+                desc: *desc,
+            };
+            Ok(Self::new(raw, deps))
+        }
+    }
+
+    pub fn desc(&self) -> &MemoryDesc {
+        &self.dependencies().desc
+    }
+}
@@ -0,0 +1,70 @@
+use crate::RawHandle;
+use ash::prelude::VkResult;
+use ash::vk;
+use std::cell::RefCell;
+use std::ffi::CString;
+use std::marker::PhantomData;
+
+thread_local! {
+    static LABEL: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+/// Names every device-level handle, created on current thread while returned scope is alive,
+/// with `label`. Nested scopes replace label until dropped.
+pub fn label_scope(label: impl Into<String>) -> LabelScope {
+    let previous = LABEL.with(|l| l.replace(Some(label.into())));
+    LabelScope {
+        previous,
+        _not_send: Default::default(),
+    }
+}
+
+/// Restores previous label on drop.
+pub struct LabelScope {
+    previous: Option<String>,
+    _not_send: PhantomData<*const ()>,
+}
+
+impl Drop for LabelScope {
+    fn drop(&mut self) {
+        let previous = self.previous.take();
+        LABEL.with(|l| *l.borrow_mut() = previous);
+    }
+}
+
+pub(crate) fn name_created<T: RawHandle>(handle: &T, deps: &T::Dependencies) {
+    LABEL.with(|l| {
+        if let Some(label) = &*l.borrow() {
+            if let Err(e) = set_name(handle, deps, label) {
+                log::warn!("Can't name {} as '{}': {}", T::name(), label, e);
+            }
+        }
+    })
+}
+
+/// Does nothing for instance-level handles and if debug utils are not loaded by `Instance`.
+pub(crate) fn set_name<T: RawHandle>(
+    handle: &T,
+    deps: &T::Dependencies,
+    name: &str,
+) -> VkResult<()> {
+    let device = match T::device(deps) {
+        Some(device) => device,
+        None => return Ok(()),
+    };
+    let debug_utils = match &device.dependencies().instance.dependencies().debug_utils {
+        Some(debug_utils) => debug_utils,
+        None => return Ok(()),
+    };
+
+    let name = name.split('\0').next().unwrap_or_default();
+    let name = CString::new(name).expect("Nul bytes are removed");
+    for raw in handle.raw_handles() {
+        let info = vk::DebugUtilsObjectNameInfoEXT::builder()
+            .object_type(T::object_type())
+            .object_handle(raw)
+            .object_name(&name);
+        unsafe { debug_utils.debug_utils_set_object_name(device.handle().handle(), &info)? }
+    }
+    Ok(())
+}
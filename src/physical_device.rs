@@ -0,0 +1,394 @@
+use crate::features::{self, DeviceFeatures};
+use crate::instance::{self, Instance};
+use crate::surface::Surface;
+use ash::prelude::VkResult;
+use ash::version::InstanceV1_0;
+use ash::vk;
+use std::error::Error;
+use std::ffi::{CStr, CString};
+use std::fmt;
+
+/// Owned description of physical device.
+#[derive(Clone)]
+///
+/// Fields are read-only, since `DeviceBuilder` trusts them to create device.
+pub struct PhysicalDeviceInfo {
+    instance: Instance,
+    handle: vk::PhysicalDevice,
+    name: String,
+    properties: vk::PhysicalDeviceProperties,
+    features: DeviceFeatures,
+    memory: vk::PhysicalDeviceMemoryProperties,
+    queue_families: Vec<vk::QueueFamilyProperties>,
+    extensions: Vec<CString>,
+}
+
+impl fmt::Debug for PhysicalDeviceInfo {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("PhysicalDeviceInfo")
+            .field("handle", &self.handle)
+            .field("name", &self.name)
+            .field("properties", &self.properties)
+            .field("features", &self.features)
+            .field("memory", &self.memory)
+            .field("queue_families", &self.queue_families)
+            .field("extensions", &self.extensions)
+            .finish()
+    }
+}
+
+impl PhysicalDeviceInfo {
+    /// Queries description of `handle`. Use `enumerate` to describe all physical devices.
+    ///
+    /// # Safety
+    /// * `handle` must be physical device, enumerated from `instance`.
+    pub unsafe fn new(instance: &Instance, handle: vk::PhysicalDevice) -> VkResult<Self> {
+        let properties = instance.get_physical_device_properties(handle);
+        let name = CStr::from_ptr(properties.device_name.as_ptr())
+            .to_string_lossy()
+            .into_owned();
+        let extensions = instance
+            .enumerate_device_extension_properties(handle)?
+            .iter()
+            .map(|e| CStr::from_ptr(e.extension_name.as_ptr()).to_owned())
+            .collect();
+
+        Ok(Self {
+            instance: instance.clone(),
+            handle,
+            name,
+            properties,
+            features: DeviceFeatures::query(instance, handle),
+            memory: instance.get_physical_device_memory_properties(handle),
+            queue_families: instance.get_physical_device_queue_family_properties(handle),
+            extensions,
+        })
+    }
+
+    /// Instance, physical device is enumerated from.
+    pub fn instance(&self) -> &Instance {
+        &self.instance
+    }
+
+    pub fn handle(&self) -> vk::PhysicalDevice {
+        self.handle
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn properties(&self) -> &vk::PhysicalDeviceProperties {
+        &self.properties
+    }
+
+    /// Features, supported by physical device.
+    pub fn features(&self) -> &DeviceFeatures {
+        &self.features
+    }
+
+    pub fn memory(&self) -> &vk::PhysicalDeviceMemoryProperties {
+        &self.memory
+    }
+
+    pub fn queue_families(&self) -> &[vk::QueueFamilyProperties] {
+        &self.queue_families
+    }
+
+    /// Names of supported device extensions.
+    pub fn extensions(&self) -> &[CString] {
+        &self.extensions
+    }
+
+    /// Vulkan version, supported by both instance and physical device.
+    pub fn api_version(&self) -> u32 {
+        // `handle` is enumerated from `instance`, as required by `new`.
+        unsafe { features::api_version(&self.instance, self.handle) }
+    }
+
+    pub fn limits(&self) -> &vk::PhysicalDeviceLimits {
+        &self.properties.limits
+    }
+
+    pub fn memory_heaps(&self) -> &[vk::MemoryHeap] {
+        &self.memory.memory_heaps[..self.memory.memory_heap_count as usize]
+    }
+
+    pub fn memory_types(&self) -> &[vk::MemoryType] {
+        &self.memory.memory_types[..self.memory.memory_type_count as usize]
+    }
+
+    pub fn supports_extension(&self, name: &CStr) -> bool {
+        self.extensions.iter().any(|e| e.as_c_str() == name)
+    }
+
+    /// Indices of queue families, supporting all of `flags`.
+    pub fn queue_families_with(&self, flags: vk::QueueFlags) -> impl Iterator<Item = u32> + '_ {
+        self.queue_families
+            .iter()
+            .enumerate()
+            .filter(move |(_, family)| family.queue_flags.contains(flags))
+            .map(|(index, _)| index as u32)
+    }
+
+    /// Indices of queue families, able to present to `surface`.
+    pub fn present_families(&self, surface: &Surface) -> VkResult<Vec<u32>> {
+        let loader = &surface.dependencies().loader;
+        let mut families = Vec::new();
+        for index in 0..self.queue_families.len() as u32 {
+            let supported = unsafe {
+                loader.get_physical_device_surface_support(self.handle, index, **surface)?
+            };
+            if supported {
+                families.push(index);
+            }
+        }
+        Ok(families)
+    }
+
+    /// Total size of device-local memory heaps.
+    pub fn device_local_memory(&self) -> vk::DeviceSize {
+        self.memory_heaps()
+            .iter()
+            .filter(|h| h.flags.contains(vk::MemoryHeapFlags::DEVICE_LOCAL))
+            .map(|h| h.size)
+            .sum()
+    }
+}
+
+pub fn enumerate(instance: &Instance) -> VkResult<Vec<PhysicalDeviceInfo>> {
+    unsafe { instance.enumerate_physical_devices()? }
+        .into_iter()
+        .map(|handle| unsafe { PhysicalDeviceInfo::new(instance, handle) })
+        .collect()
+}
+
+/// Prefers discrete devices, then devices with more device-local memory.
+pub fn default_score(info: &PhysicalDeviceInfo) -> i64 {
+    let type_score = match info.properties.device_type {
+        vk::PhysicalDeviceType::DISCRETE_GPU => 10_000,
+        vk::PhysicalDeviceType::INTEGRATED_GPU => 5_000,
+        vk::PhysicalDeviceType::VIRTUAL_GPU => 1_000,
+        vk::PhysicalDeviceType::CPU => 100,
+        _ => 0,
+    };
+    let memory_gib = (info.device_local_memory() >> 30) as i64;
+    type_score + memory_gib
+}
+
+type Requirement = (String, Box<dyn Fn(&PhysicalDeviceInfo) -> bool>);
+
+/// Selects best physical device, satisfying requirements.
+pub struct DeviceSelector {
+    min_api_version: u32,
+    extensions: Vec<CString>,
+    features: DeviceFeatures,
+    queues: Vec<vk::QueueFlags>,
+    surface: Option<Surface>,
+    requirements: Vec<Requirement>,
+    score: Box<dyn Fn(&PhysicalDeviceInfo) -> i64>,
+}
+
+impl Default for DeviceSelector {
+    fn default() -> Self {
+        Self {
+            min_api_version: vk::API_VERSION_1_0,
+            extensions: Vec::new(),
+            features: Default::default(),
+            queues: Vec::new(),
+            surface: None,
+            requirements: Vec::new(),
+            score: Box::new(default_score),
+        }
+    }
+}
+
+impl DeviceSelector {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Requires Vulkan `version`, supported by both instance and device.
+    pub fn min_api_version(mut self, version: u32) -> Self {
+        self.min_api_version = version;
+        self
+    }
+
+    pub fn require_extension(mut self, name: &CStr) -> Self {
+        self.extensions.push(name.to_owned());
+        self
+    }
+
+    /// Requires `features`. Adds to previously required ones.
+    pub fn require_features(mut self, features: DeviceFeatures) -> Self {
+        self.features = self.features.union(&features);
+        self
+    }
+
+    /// Requires queue family, supporting all of `flags`.
+    pub fn require_queue(mut self, flags: vk::QueueFlags) -> Self {
+        self.queues.push(flags);
+        self
+    }
+
+    /// Requires queue family, able to present to `surface`.
+    pub fn require_present(mut self, surface: &Surface) -> Self {
+        self.surface = Some(surface.clone());
+        self
+    }
+
+    /// Custom requirement. `description` is reported for devices, not satisfying `check`.
+    pub fn require(
+        mut self,
+        description: &str,
+        check: impl Fn(&PhysicalDeviceInfo) -> bool + 'static,
+    ) -> Self {
+        self.requirements
+            .push((description.into(), Box::new(check)));
+        self
+    }
+
+    /// Replaces `default_score`. Device with highest score is selected.
+    pub fn score(mut self, score: impl Fn(&PhysicalDeviceInfo) -> i64 + 'static) -> Self {
+        self.score = Box::new(score);
+        self
+    }
+
+    pub fn select(&self, instance: &Instance) -> Result<Selection, SelectError> {
+        self.select_from(enumerate(instance)?)
+    }
+
+    pub fn select_from(&self, devices: Vec<PhysicalDeviceInfo>) -> Result<Selection, SelectError> {
+        let mut best: Option<(PhysicalDeviceInfo, i64)> = None;
+        let mut rejected = Vec::new();
+
+        for device in devices {
+            let reasons = self.rejection_reasons(&device)?;
+            if !reasons.is_empty() {
+                rejected.push(Rejected { device, reasons });
+                continue;
+            }
+
+            let score = (self.score)(&device);
+            match &best {
+                Some((_, best_score)) if *best_score >= score => {
+                    let reasons =
+                        vec![format!("score {} is not higher than {}", score, best_score)];
+                    rejected.push(Rejected { device, reasons });
+                }
+                _ => {
+                    if let Some((previous, previous_score)) = best.take() {
+                        let reasons =
+                            vec![format!("score {} is lower than {}", previous_score, score)];
+                        rejected.push(Rejected {
+                            device: previous,
+                            reasons,
+                        });
+                    }
+                    best = Some((device, score));
+                }
+            }
+        }
+
+        match best {
+            Some((device, score)) => Ok(Selection {
+                device,
+                score,
+                rejected,
+            }),
+            None => Err(SelectError::NoSuitableDevice(rejected)),
+        }
+    }
+
+    fn rejection_reasons(&self, device: &PhysicalDeviceInfo) -> VkResult<Vec<String>> {
+        let mut reasons = Vec::new();
+
+        let api_version = device.api_version();
+        if api_version < self.min_api_version {
+            reasons.push(format!(
+                "API version {} of instance and device is lower than required {}",
+                instance::version_string(api_version),
+                instance::version_string(self.min_api_version)
+            ));
+        }
+
+        for ext in &self.extensions {
+            if !device.supports_extension(ext) {
+                reasons.push(format!("missing extension {}", ext.to_string_lossy()));
+            }
+        }
+
+        for name in self.features.unsupported(&device.features) {
+            reasons.push(format!("missing feature {}", name));
+        }
+
+        for &flags in &self.queues {
+            if device.queue_families_with(flags).next().is_none() {
+                reasons.push(format!("no queue family with {:?}", flags));
+            }
+        }
+
+        if let Some(surface) = &self.surface {
+            if device.present_families(surface)?.is_empty() {
+                reasons.push("no queue family can present to surface".into());
+            }
+        }
+
+        for (description, check) in &self.requirements {
+            if !check(device) {
+                reasons.push(description.clone());
+            }
+        }
+
+        Ok(reasons)
+    }
+}
+
+/// Device, not selected by `DeviceSelector`.
+#[derive(Debug, Clone)]
+pub struct Rejected {
+    pub device: PhysicalDeviceInfo,
+    pub reasons: Vec<String>,
+}
+
+impl fmt::Display for Rejected {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.device.name, self.reasons.join(", "))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Selection {
+    pub device: PhysicalDeviceInfo,
+    pub score: i64,
+    pub rejected: Vec<Rejected>,
+}
+
+#[derive(Debug)]
+pub enum SelectError {
+    NoSuitableDevice(Vec<Rejected>),
+    Vulkan(vk::Result),
+}
+
+impl From<vk::Result> for SelectError {
+    fn from(e: vk::Result) -> Self {
+        Self::Vulkan(e)
+    }
+}
+
+impl Error for SelectError {}
+
+impl fmt::Display for SelectError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SelectError::NoSuitableDevice(rejected) => {
+                write!(f, "No suitable physical device")?;
+                for r in rejected {
+                    write!(f, "; {}", r)?;
+                }
+                Ok(())
+            }
+            SelectError::Vulkan(e) => write!(f, "Can't query physical devices: {}", e),
+        }
+    }
+}
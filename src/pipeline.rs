@@ -1,7 +1,12 @@
+use crate::device::Device;
+use crate::pipeline_cache::PipelineCache;
 use crate::pipeline_layout::PipelineLayout;
+use crate::shader_module::ShaderModule;
 use crate::{Handle, RawHandle};
+use ash::prelude::VkResult;
 use ash::version::DeviceV1_0;
 use ash::vk;
+use std::ffi::CStr;
 
 pub struct Deps {
     pub layout: PipelineLayout,
@@ -14,6 +19,18 @@ impl RawHandle for vk::Pipeline {
         "pipeline"
     }
 
+    fn device(deps: &Self::Dependencies) -> Option<&Device> {
+        Some(&deps.layout.dependencies().device)
+    }
+
+    fn object_type() -> vk::ObjectType {
+        vk::ObjectType::PIPELINE
+    }
+
+    fn raw_handles(&self) -> Vec<u64> {
+        vec![vk::Handle::as_raw(*self)]
+    }
+
     fn destroy(&self, deps: &Self::Dependencies) {
         let device = &deps.layout.dependencies().device;
         unsafe { device.destroy_pipeline(*self, None) }
@@ -21,3 +38,52 @@ impl RawHandle for vk::Pipeline {
 }
 
 pub type Pipeline = Handle<vk::Pipeline, Deps>;
+
+/// Values of specialization constants.
+#[derive(Debug, Copy, Clone)]
+pub struct Specialization<'a> {
+    pub map_entries: &'a [vk::SpecializationMapEntry],
+    pub data: &'a [u8],
+}
+
+impl Pipeline {
+    pub fn create_compute(
+        layout: &PipelineLayout,
+        shader: &ShaderModule,
+        entry_point: &CStr,
+        specialization: Option<Specialization>,
+        cache: Option<&PipelineCache>,
+    ) -> VkResult<Self> {
+        let spec_info = specialization.map(|spec| {
+            vk::SpecializationInfo::builder()
+                .map_entries(spec.map_entries)
+                .data(spec.data)
+        });
+
+        let mut stage_ci = vk::PipelineShaderStageCreateInfo::builder()
+            .stage(vk::ShaderStageFlags::COMPUTE)
+            .module(**shader)
+            .name(entry_point);
+        if let Some(spec_info) = &spec_info {
+            stage_ci = stage_ci.specialization_info(spec_info);
+        }
+
+        let ci = vk::ComputePipelineCreateInfo::builder()
+            .base_pipeline_index(-1)
+            .layout(**layout)
+            .stage(stage_ci.build());
+
+        let raw_cache = cache.map(|c| **c).unwrap_or_default();
+        let device = &layout.dependencies().device;
+        unsafe {
+            let raw = device
+                .create_compute_pipelines(raw_cache, &[ci.build()], None)
+                .map_err(|(_, e)| e)?
+                .remove(0);
+            let deps = Deps {
+                layout: layout.clone(),
+            };
+            Ok(Self::new(raw, deps))
+        }
+    }
+}
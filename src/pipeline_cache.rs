@@ -1,5 +1,6 @@
 use crate::device::Device;
 use crate::{Handle, RawHandle};
+use ash::prelude::VkResult;
 use ash::version::DeviceV1_0;
 use ash::vk;
 
@@ -14,9 +15,40 @@ impl RawHandle for vk::PipelineCache {
         "pipeline cache"
     }
 
+    fn device(deps: &Self::Dependencies) -> Option<&Device> {
+        Some(&deps.device)
+    }
+
+    fn object_type() -> vk::ObjectType {
+        vk::ObjectType::PIPELINE_CACHE
+    }
+
+    fn raw_handles(&self) -> Vec<u64> {
+        vec![vk::Handle::as_raw(*self)]
+    }
+
     fn destroy(&self, dependencies: &Self::Dependencies) {
         unsafe { dependencies.device.destroy_pipeline_cache(*self, None) }
     }
 }
 
 pub type PipelineCache = Handle<vk::PipelineCache, Deps>;
+
+impl PipelineCache {
+    /// Creates pipeline cache, filled with `initial_data`, previously retrieved from other cache.
+    pub fn create(device: &Device, initial_data: &[u8]) -> VkResult<Self> {
+        let ci = vk::PipelineCacheCreateInfo::builder().initial_data(initial_data);
+        unsafe {
+            let raw = device.create_pipeline_cache(&ci, None)?;
+            let deps = Deps {
+                device: device.clone(),
+            };
+            Ok(Self::new(raw, deps))
+        }
+    }
+
+    /// Data of cache, which can be used to create it again.
+    pub fn data(&self) -> VkResult<Vec<u8>> {
+        unsafe { self.dependencies().device.get_pipeline_cache_data(**self) }
+    }
+}
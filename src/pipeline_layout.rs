@@ -1,12 +1,34 @@
 use crate::device::Device;
+use crate::ds_layout::DescriptorSetLayout;
 use crate::{Handle, RawHandle};
+use ash::prelude::VkResult;
 use ash::version::DeviceV1_0;
 use ash::vk;
-use crate::ds_layout::DescriptorSetLayout;
+use std::hash::Hash;
+
+/// Parameters of pipeline layout.
+#[derive(Debug, Clone, Default)]
+pub struct PipelineLayoutDesc {
+    pub ds_layouts: Vec<DescriptorSetLayout>,
+    pub push_constant_ranges: Vec<vk::PushConstantRange>,
+}
+
+impl PipelineLayoutDesc {
+    fn key(&self) -> impl Eq + Hash + '_ {
+        let ranges: Vec<_> = self
+            .push_constant_ranges
+            .iter()
+            .map(|r| (r.stage_flags, r.offset, r.size))
+            .collect();
+        (&self.ds_layouts, ranges)
+    }
+}
+
+impl_eq_hash_by_key!(PipelineLayoutDesc);
 
 pub struct Deps {
     pub device: Device,
-    pub ds_layouts: Vec<DescriptorSetLayout>
+    pub desc: PipelineLayoutDesc,
 }
 
 impl RawHandle for vk::PipelineLayout {
@@ -16,9 +38,47 @@ impl RawHandle for vk::PipelineLayout {
         "pipeline layout"
     }
 
+    fn device(deps: &Self::Dependencies) -> Option<&Device> {
+        Some(&deps.device)
+    }
+
+    fn object_type() -> vk::ObjectType {
+        vk::ObjectType::PIPELINE_LAYOUT
+    }
+
+    fn raw_handles(&self) -> Vec<u64> {
+        vec![vk::Handle::as_raw(*self)]
+    }
+
     fn destroy(&self, dependencies: &Self::Dependencies) {
         unsafe { dependencies.device.destroy_pipeline_layout(*self, None) }
     }
 }
 
 pub type PipelineLayout = Handle<vk::PipelineLayout, Deps>;
+
+impl PipelineLayout {
+    pub fn create(
+        device: &Device,
+        ds_layouts: &[DescriptorSetLayout],
+        push_constant_ranges: &[vk::PushConstantRange],
+    ) -> VkResult<Self> {
+        let raw_ds_layouts: Vec<vk::DescriptorSetLayout> =
+            ds_layouts.iter().map(|dsl| **dsl).collect();
+        let ci = vk::PipelineLayoutCreateInfo::builder()
+            .set_layouts(&raw_ds_layouts)
+            .push_constant_ranges(push_constant_ranges);
+
+        unsafe {
+            let raw = device.create_pipeline_layout(&ci, None)?;
+            let deps = Deps {
+                device: device.clone(),
+                desc: PipelineLayoutDesc {
+                    ds_layouts: ds_layouts.to_vec(),
+                    push_constant_ranges: push_constant_ranges.to_vec(),
+                },
+            };
+            Ok(Self::new(raw, deps))
+        }
+    }
+}
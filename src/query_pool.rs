@@ -0,0 +1,505 @@
+use crate::command_buffer::CommandBuffer;
+use crate::device::Device;
+use crate::queue::Queue;
+use crate::{Handle, RawHandle};
+use ash::prelude::VkResult;
+use ash::version::{DeviceV1_0, InstanceV1_0};
+use ash::vk;
+use std::marker::PhantomData;
+use std::mem;
+use std::time::Duration;
+
+/// Kind of queries in pool. Defines how raw query results are decoded.
+pub trait QueryKind {
+    type Output;
+
+    fn query_type() -> vk::QueryType;
+
+    /// Count of `u64` values, written by one query.
+    fn values_per_query(deps: &Deps) -> usize;
+
+    fn decode(values: &[u64], ctx: &DecodeContext) -> Self::Output;
+}
+
+/// Kind of queries, which pool is created without pipeline statistics.
+pub trait SimpleQueryKind: QueryKind {}
+
+/// Data, required to decode query results.
+pub struct DecodeContext {
+    /// Nanoseconds per timestamp tick.
+    pub timestamp_period: f32,
+    /// Count of meaningful low bits of timestamps.
+    pub timestamp_valid_bits: u32,
+    pub statistics: vk::QueryPipelineStatisticFlags,
+}
+
+/// Timestamp queries. Results are durations since unspecified moment, scaled by `timestampPeriod`.
+pub struct TimestampQuery;
+
+impl QueryKind for TimestampQuery {
+    type Output = Duration;
+
+    fn query_type() -> vk::QueryType {
+        vk::QueryType::TIMESTAMP
+    }
+
+    fn values_per_query(_: &Deps) -> usize {
+        1
+    }
+
+    fn decode(values: &[u64], ctx: &DecodeContext) -> Self::Output {
+        let mask = match ctx.timestamp_valid_bits {
+            bits if bits >= 64 => u64::MAX,
+            bits => (1 << bits) - 1,
+        };
+        let nanos = (values[0] & mask) as f64 * ctx.timestamp_period as f64;
+        Duration::from_nanos(nanos as u64)
+    }
+}
+
+impl SimpleQueryKind for TimestampQuery {}
+
+/// Occlusion queries. Results are counts of passed samples.
+pub struct OcclusionQuery;
+
+impl QueryKind for OcclusionQuery {
+    type Output = u64;
+
+    fn query_type() -> vk::QueryType {
+        vk::QueryType::OCCLUSION
+    }
+
+    fn values_per_query(_: &Deps) -> usize {
+        1
+    }
+
+    fn decode(values: &[u64], _: &DecodeContext) -> Self::Output {
+        values[0]
+    }
+}
+
+impl SimpleQueryKind for OcclusionQuery {}
+
+/// Pipeline statistics queries. Collected statistics are set by `Deps::statistics`.
+pub struct PipelineStatisticsQuery;
+
+impl QueryKind for PipelineStatisticsQuery {
+    type Output = PipelineStatistics;
+
+    fn query_type() -> vk::QueryType {
+        vk::QueryType::PIPELINE_STATISTICS
+    }
+
+    fn values_per_query(deps: &Deps) -> usize {
+        deps.statistics.as_raw().count_ones() as usize
+    }
+
+    fn decode(values: &[u64], ctx: &DecodeContext) -> Self::Output {
+        PipelineStatistics::decode(values, ctx.statistics)
+    }
+}
+
+/// Pipeline statistics values. Statistics, not collected by pool, are `None`.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct PipelineStatistics {
+    pub input_assembly_vertices: Option<u64>,
+    pub input_assembly_primitives: Option<u64>,
+    pub vertex_shader_invocations: Option<u64>,
+    pub geometry_shader_invocations: Option<u64>,
+    pub geometry_shader_primitives: Option<u64>,
+    pub clipping_invocations: Option<u64>,
+    pub clipping_primitives: Option<u64>,
+    pub fragment_shader_invocations: Option<u64>,
+    pub tessellation_control_shader_patches: Option<u64>,
+    pub tessellation_evaluation_shader_invocations: Option<u64>,
+    pub compute_shader_invocations: Option<u64>,
+}
+
+impl PipelineStatistics {
+    /// Values are written in order of statistic flag bits.
+    fn decode(values: &[u64], statistics: vk::QueryPipelineStatisticFlags) -> Self {
+        type Flags = vk::QueryPipelineStatisticFlags;
+        let mut result = Self::default();
+        let mut values = values.iter().copied();
+        let fields = [
+            (
+                Flags::INPUT_ASSEMBLY_VERTICES,
+                &mut result.input_assembly_vertices,
+            ),
+            (
+                Flags::INPUT_ASSEMBLY_PRIMITIVES,
+                &mut result.input_assembly_primitives,
+            ),
+            (
+                Flags::VERTEX_SHADER_INVOCATIONS,
+                &mut result.vertex_shader_invocations,
+            ),
+            (
+                Flags::GEOMETRY_SHADER_INVOCATIONS,
+                &mut result.geometry_shader_invocations,
+            ),
+            (
+                Flags::GEOMETRY_SHADER_PRIMITIVES,
+                &mut result.geometry_shader_primitives,
+            ),
+            (
+                Flags::CLIPPING_INVOCATIONS,
+                &mut result.clipping_invocations,
+            ),
+            (Flags::CLIPPING_PRIMITIVES, &mut result.clipping_primitives),
+            (
+                Flags::FRAGMENT_SHADER_INVOCATIONS,
+                &mut result.fragment_shader_invocations,
+            ),
+            (
+                Flags::TESSELLATION_CONTROL_SHADER_PATCHES,
+                &mut result.tessellation_control_shader_patches,
+            ),
+            (
+                Flags::TESSELLATION_EVALUATION_SHADER_INVOCATIONS,
+                &mut result.tessellation_evaluation_shader_invocations,
+            ),
+            (
+                Flags::COMPUTE_SHADER_INVOCATIONS,
+                &mut result.compute_shader_invocations,
+            ),
+        ];
+
+        for (flag, field) in fields {
+            if statistics.contains(flag) {
+                *field = values.next();
+            }
+        }
+
+        result
+    }
+}
+
+pub struct RawQueryPool<Kind> {
+    handle: vk::QueryPool,
+    _kind: PhantomData<Kind>,
+}
+
+impl<Kind> RawQueryPool<Kind> {
+    pub fn new(handle: vk::QueryPool) -> Self {
+        Self {
+            handle,
+            _kind: Default::default(),
+        }
+    }
+
+    pub fn handle(&self) -> &vk::QueryPool {
+        &self.handle
+    }
+}
+
+pub struct Deps {
+    pub device: Device,
+    pub query_count: u32,
+    /// Collected pipeline statistics. Used by `PipelineStatisticsQuery` pools only.
+    pub statistics: vk::QueryPipelineStatisticFlags,
+    /// Valid bits of timestamps, written by queue family. Used by `TimestampQuery` pools only.
+    pub timestamp_valid_bits: u32,
+}
+
+impl<Kind> RawHandle for RawQueryPool<Kind> {
+    type Dependencies = Deps;
+
+    fn name() -> &'static str {
+        "query pool"
+    }
+
+    fn device(deps: &Self::Dependencies) -> Option<&Device> {
+        Some(&deps.device)
+    }
+
+    fn object_type() -> vk::ObjectType {
+        vk::ObjectType::QUERY_POOL
+    }
+
+    fn raw_handles(&self) -> Vec<u64> {
+        vec![vk::Handle::as_raw(self.handle)]
+    }
+
+    fn destroy(&self, deps: &Self::Dependencies) {
+        unsafe { deps.device.destroy_query_pool(self.handle, None) }
+    }
+}
+
+pub type QueryPool<Kind> = Handle<RawQueryPool<Kind>, Deps>;
+
+impl<Kind: SimpleQueryKind> QueryPool<Kind> {
+    /// Creates pool of `query_count` queries. Pipeline statistics pools are created by
+    /// `create_statistics`. Timestamp pools, created here, treat all 64 bits of timestamps as
+    /// valid. Use `create_timestamps` to mask them for queue family.
+    pub fn create(device: &Device, query_count: u32) -> VkResult<Self> {
+        Self::create_raw(
+            device,
+            query_count,
+            vk::QueryPipelineStatisticFlags::empty(),
+            64,
+        )
+    }
+}
+
+impl<Kind: QueryKind> QueryPool<Kind> {
+    fn create_raw(
+        device: &Device,
+        query_count: u32,
+        statistics: vk::QueryPipelineStatisticFlags,
+        timestamp_valid_bits: u32,
+    ) -> VkResult<Self> {
+        let ci = vk::QueryPoolCreateInfo::builder()
+            .query_type(Kind::query_type())
+            .query_count(query_count)
+            .pipeline_statistics(statistics);
+
+        unsafe {
+            let raw = device.create_query_pool(&ci, None)?;
+            let deps = Deps {
+                device: device.clone(),
+                query_count,
+                statistics,
+                timestamp_valid_bits,
+            };
+            Ok(Self::new(RawQueryPool::new(raw), deps))
+        }
+    }
+
+    /// Returns results of `count` queries, starting from `first`.
+    /// Results of queries, which are not available yet, are `None`.
+    ///
+    /// # Panics
+    /// * if queries range is out of pool.
+    pub fn get_results(&self, first: u32, count: u32) -> VkResult<Vec<Option<Kind::Output>>> {
+        self.read_results(first, count, vk::QueryResultFlags::empty())
+    }
+
+    /// Waits for results of `count` queries, starting from `first`, and returns them.
+    ///
+    /// # Panics
+    /// * if queries range is out of pool.
+    pub fn wait_results(&self, first: u32, count: u32) -> VkResult<Vec<Kind::Output>> {
+        let results = self.read_results(first, count, vk::QueryResultFlags::WAIT)?;
+        Ok(results.into_iter().flatten().collect())
+    }
+
+    /// Records reset of `count` queries, starting from `first`, to `command_buffer`.
+    ///
+    /// # Safety
+    /// * `command_buffer` must be in recording state.
+    /// * `command_buffer` must not be accessed by other threads during the call.
+    ///
+    /// # Panics
+    /// * if queries range is out of pool.
+    pub unsafe fn cmd_reset(&self, command_buffer: &CommandBuffer, first: u32, count: u32) {
+        self.check_range(first, count);
+        let device = &self.dependencies().device;
+        let pool = *self.handle().handle();
+        device.cmd_reset_query_pool(**command_buffer, pool, first, count)
+    }
+
+    fn read_results(
+        &self,
+        first: u32,
+        count: u32,
+        flags: vk::QueryResultFlags,
+    ) -> VkResult<Vec<Option<Kind::Output>>> {
+        self.check_range(first, count);
+        let deps = self.dependencies();
+        let device = &deps.device;
+        let values_per_query = Kind::values_per_query(deps);
+        let stride = values_per_query + 1;
+        let mut data = vec![0u64; stride * count as usize];
+        let flags = flags | vk::QueryResultFlags::TYPE_64 | vk::QueryResultFlags::WITH_AVAILABILITY;
+
+        let result = unsafe {
+            device.fp_v1_0().get_query_pool_results(
+                device.handle().handle(),
+                *self.handle().handle(),
+                first,
+                count,
+                data.len() * mem::size_of::<u64>(),
+                data.as_mut_ptr().cast(),
+                (stride * mem::size_of::<u64>()) as vk::DeviceSize,
+                flags,
+            )
+        };
+
+        match result {
+            vk::Result::SUCCESS | vk::Result::NOT_READY => {}
+            e => return Err(e),
+        }
+
+        let ctx = self.decode_context();
+        let results = data
+            .chunks_exact(stride)
+            .map(|chunk| {
+                let (values, availability) = chunk.split_at(values_per_query);
+                match availability[0] {
+                    0 => None,
+                    _ => Some(Kind::decode(values, &ctx)),
+                }
+            })
+            .collect();
+
+        Ok(results)
+    }
+
+    fn decode_context(&self) -> DecodeContext {
+        let deps = self.dependencies();
+        let device_deps = deps.device.dependencies();
+        let properties = unsafe {
+            device_deps
+                .instance
+                .get_physical_device_properties(device_deps.pdevice)
+        };
+
+        DecodeContext {
+            timestamp_period: properties.limits.timestamp_period,
+            timestamp_valid_bits: deps.timestamp_valid_bits,
+            statistics: deps.statistics,
+        }
+    }
+
+    fn check_range(&self, first: u32, count: u32) {
+        let in_range = matches!(
+            first.checked_add(count),
+            Some(end) if end <= self.dependencies().query_count
+        );
+        assert!(in_range, "Queries range is out of pool");
+    }
+}
+
+impl QueryPool<PipelineStatisticsQuery> {
+    /// Creates pool of `query_count` queries, collecting `statistics`.
+    pub fn create_statistics(
+        device: &Device,
+        query_count: u32,
+        statistics: vk::QueryPipelineStatisticFlags,
+    ) -> VkResult<Self> {
+        Self::create_raw(device, query_count, statistics, 0)
+    }
+}
+
+impl QueryPool<TimestampQuery> {
+    /// Creates pool of `query_count` timestamp queries, written by command buffers of `queue`
+    /// family. Timestamps are masked with `timestamp_valid_bits` of the family.
+    pub fn create_timestamps(queue: &Queue, query_count: u32) -> VkResult<Self> {
+        let deps = queue.dependencies();
+        let device = &deps.device;
+        let device_deps = device.dependencies();
+        let families = unsafe {
+            device_deps
+                .instance
+                .get_physical_device_queue_family_properties(device_deps.pdevice)
+        };
+        let valid_bits = families[deps.family_index as usize].timestamp_valid_bits;
+        Self::create_raw(
+            device,
+            query_count,
+            vk::QueryPipelineStatisticFlags::empty(),
+            valid_bits,
+        )
+    }
+
+    /// Records write of timestamp to `query` after `stage` of commands is completed.
+    ///
+    /// # Safety
+    /// * `command_buffer` must be in recording state.
+    /// * `command_buffer` must not be accessed by other threads during the call.
+    /// * `query` must be reset and not written since.
+    ///
+    /// # Panics
+    /// * if `query` is out of pool.
+    pub unsafe fn cmd_write_timestamp(
+        &self,
+        command_buffer: &CommandBuffer,
+        stage: vk::PipelineStageFlags,
+        query: u32,
+    ) {
+        self.check_range(query, 1);
+        let device = &self.dependencies().device;
+        let pool = *self.handle().handle();
+        device.cmd_write_timestamp(**command_buffer, stage, pool, query)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn context(timestamp_valid_bits: u32) -> DecodeContext {
+        DecodeContext {
+            timestamp_period: 1.0,
+            timestamp_valid_bits,
+            statistics: vk::QueryPipelineStatisticFlags::empty(),
+        }
+    }
+
+    #[test]
+    fn timestamp_masks_invalid_bits() {
+        let ctx = context(36);
+        let value = (0xABCD << 36) | 1000;
+        assert_eq!(
+            TimestampQuery::decode(&[value], &ctx),
+            Duration::from_nanos(1000)
+        );
+    }
+
+    #[test]
+    fn timestamp_keeps_all_bits_if_valid() {
+        let ctx = context(64);
+        let value = 1 << 40;
+        assert_eq!(
+            TimestampQuery::decode(&[value], &ctx),
+            Duration::from_nanos(value)
+        );
+    }
+
+    #[test]
+    fn timestamp_scales_by_period() {
+        let ctx = DecodeContext {
+            timestamp_period: 2.5,
+            ..context(64)
+        };
+        assert_eq!(TimestampQuery::decode(&[4], &ctx), Duration::from_nanos(10));
+    }
+
+    #[test]
+    fn statistics_follow_flag_order() {
+        type Flags = vk::QueryPipelineStatisticFlags;
+        // Flags are set out of order, values are written in order of flag bits.
+        let statistics = Flags::COMPUTE_SHADER_INVOCATIONS
+            | Flags::INPUT_ASSEMBLY_VERTICES
+            | Flags::FRAGMENT_SHADER_INVOCATIONS;
+        let stats = PipelineStatistics::decode(&[1, 2, 3], statistics);
+        assert_eq!(
+            stats,
+            PipelineStatistics {
+                input_assembly_vertices: Some(1),
+                fragment_shader_invocations: Some(2),
+                compute_shader_invocations: Some(3),
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn statistics_decode_all_flags() {
+        let statistics = vk::QueryPipelineStatisticFlags::all();
+        let values: Vec<u64> = (0..11).collect();
+        let stats = PipelineStatistics::decode(&values, statistics);
+        assert_eq!(stats.input_assembly_vertices, Some(0));
+        assert_eq!(stats.clipping_primitives, Some(6));
+        assert_eq!(stats.tessellation_control_shader_patches, Some(8));
+        assert_eq!(stats.compute_shader_invocations, Some(10));
+    }
+
+    #[test]
+    fn statistics_without_flags_are_empty() {
+        let statistics = vk::QueryPipelineStatisticFlags::empty();
+        let stats = PipelineStatistics::decode(&[], statistics);
+        assert_eq!(stats, PipelineStatistics::default());
+    }
+}
@@ -1,11 +1,120 @@
+use crate::command_buffer::CommandBuffer;
 use crate::device::Device;
-use crate::{Handle, RawHandle};
+use crate::fence::Fence;
+use crate::semaphore::{Semaphore, TimelineApi, TimelineSemaphore};
+use crate::swapchain::Swapchain;
+use crate::{Handle, RawHandle, WaitResult};
+use ash::prelude::VkResult;
+use ash::version::DeviceV1_0;
 use ash::vk;
+use std::sync::{Mutex, MutexGuard};
+
+/// Identifier of submission, returned by `Queue::submit`. Grows with each submission.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SubmissionId(u64);
+
+impl SubmissionId {
+    pub fn value(&self) -> u64 {
+        self.0
+    }
+}
+
+/// Signaled on submission completion.
+enum Marker {
+    /// Fence and whether it is owned by queue and may be reused.
+    Fence(Fence, bool),
+    /// Value of queue timeline semaphore.
+    Timeline(u64),
+}
+
+/// Handles, used by submission. Kept alive until it completes.
+struct Pending {
+    id: SubmissionId,
+    marker: Marker,
+    _fence: Option<Fence>,
+    _command_buffers: Vec<CommandBuffer>,
+    _semaphores: Vec<Semaphore>,
+    _resources: Vec<Box<dyn Send>>,
+}
+
+#[derive(Default)]
+struct State {
+    last_id: u64,
+    pending: Vec<Pending>,
+    /// Created on first submission, if device has `timeline_semaphore` feature enabled.
+    timeline: Option<TimelineSemaphore>,
+    timeline_checked: bool,
+    free_fences: Vec<Fence>,
+}
+
+impl State {
+    fn completed_value(&self) -> VkResult<u64> {
+        match &self.timeline {
+            Some(timeline) => timeline.counter_value(),
+            None => Ok(0),
+        }
+    }
+
+    /// Removes completed submissions and recycles their fences.
+    fn take_completed(&mut self) -> VkResult<Vec<Pending>> {
+        let completed_value = self.completed_value()?;
+        let mut completed = Vec::new();
+        let mut index = 0;
+        while index < self.pending.len() {
+            let is_complete = match &self.pending[index].marker {
+                Marker::Fence(fence, _) => fence.is_signaled()?,
+                Marker::Timeline(value) => *value <= completed_value,
+            };
+            if is_complete {
+                completed.push(self.pending.swap_remove(index));
+            } else {
+                index += 1;
+            }
+        }
+
+        self.recycle_fences(&completed)?;
+        Ok(completed)
+    }
+
+    /// Removes all submissions, e.g. after queue became idle, and recycles their fences.
+    /// Markers are not checked, since fences, reset by user, are never signaled.
+    fn take_all(&mut self) -> VkResult<Vec<Pending>> {
+        let completed: Vec<_> = self.pending.drain(..).collect();
+        self.recycle_fences(&completed)?;
+        Ok(completed)
+    }
+
+    fn recycle_fences(&mut self, completed: &[Pending]) -> VkResult<()> {
+        for pending in completed {
+            if let Marker::Fence(fence, true) = &pending.marker {
+                fence.reset()?;
+                self.free_fences.push(fence.clone());
+            }
+        }
+        Ok(())
+    }
+}
 
 pub struct Deps {
     pub device: Device,
     pub family_index: u32,
     pub queue_index: u32,
+    state: Mutex<State>,
+}
+
+impl Deps {
+    pub fn new(device: Device, family_index: u32, queue_index: u32) -> Self {
+        Self {
+            device,
+            family_index,
+            queue_index,
+            state: Default::default(),
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
 }
 
 impl RawHandle for vk::Queue {
@@ -15,7 +124,287 @@ impl RawHandle for vk::Queue {
         "queue"
     }
 
-    fn destroy(&self, _: &Self::Dependencies) {}
+    fn device(deps: &Self::Dependencies) -> Option<&Device> {
+        Some(&deps.device)
+    }
+
+    fn object_type() -> vk::ObjectType {
+        vk::ObjectType::QUEUE
+    }
+
+    fn raw_handles(&self) -> Vec<u64> {
+        vec![vk::Handle::as_raw(*self)]
+    }
+
+    /// Waits for pending submissions, so that handles used by them may be destroyed.
+    fn destroy(&self, deps: &Self::Dependencies) {
+        if deps.lock().pending.is_empty() {
+            return;
+        }
+
+        let _queues = deps.device.dependencies().queue_access();
+
+        if let Err(e) = unsafe { deps.device.queue_wait_idle(*self) } {
+            log::error!("Can't wait for queue idle before destroying it: {}", e);
+        }
+    }
 }
 
+/// Queue with access synchronized between clones.
+///
+/// Queue synchronizes host access and keeps submitted handles alive, but can't validate
+/// commands and semaphore states, so `submit` and `present` are `unsafe`.
 pub type Queue = Handle<vk::Queue, Deps>;
+
+/// Batch of command buffers for `Queue::submit`.
+#[derive(Default)]
+pub struct Submission {
+    /// Semaphores to wait for and stages, where waiting happens.
+    pub wait: Vec<(Semaphore, vk::PipelineStageFlags)>,
+    pub command_buffers: Vec<CommandBuffer>,
+    pub signal: Vec<Semaphore>,
+    /// Handles, used by command buffers, e.g. buffers and images.
+    ///
+    /// Must not hold clone of the submitting `Queue`, directly or through other values.
+    /// Resources are released only when queue observes submission complete, so such clone makes
+    /// reference cycle: queue is never destroyed if it is not polled after other clones are
+    /// dropped.
+    pub resources: Vec<Box<dyn Send>>,
+}
+
+impl Submission {
+    /// Keeps `resource` alive until submission completes.
+    /// `resource` must not hold clone of the submitting `Queue`, see `resources`.
+    pub fn keep_alive(mut self, resource: impl Send + 'static) -> Self {
+        self.resources.push(Box::new(resource));
+        self
+    }
+}
+
+impl Queue {
+    /// Submits `submissions`, signaling `fence` on completion.
+    ///
+    /// Submitted handles are kept alive until returned submission is observed complete by
+    /// `poll`, `wait`, `is_complete` or `wait_idle`. Completion is tracked by queue timeline
+    /// semaphore, if device has `timeline_semaphore` feature enabled, or by fence otherwise.
+    ///
+    /// # Safety
+    /// * `command_buffers` must be in executable state: recorded and not being recorded.
+    /// * command buffers, recorded without `SIMULTANEOUS_USE` flag, must not be pending
+    ///   in other submission.
+    /// * `wait` semaphores must be signaled or have pending signal operations.
+    /// * resources, used by command buffers, must be kept alive, e.g. by `keep_alive`.
+    pub unsafe fn submit(
+        &self,
+        submissions: Vec<Submission>,
+        fence: Option<&Fence>,
+    ) -> VkResult<SubmissionId> {
+        let deps = self.dependencies();
+        let mut raw: Vec<_> = submissions
+            .iter()
+            .map(|s| {
+                let wait_semaphores: Vec<_> = s.wait.iter().map(|(sem, _)| **sem).collect();
+                let wait_stages: Vec<_> = s.wait.iter().map(|(_, stage)| *stage).collect();
+                let command_buffers: Vec<_> = s.command_buffers.iter().map(|cb| **cb).collect();
+                let signal_semaphores: Vec<_> = s.signal.iter().map(|sem| **sem).collect();
+                (
+                    wait_semaphores,
+                    wait_stages,
+                    command_buffers,
+                    signal_semaphores,
+                )
+            })
+            .collect();
+
+        let queues = deps.device.dependencies().queue_access();
+        let mut state = deps.lock();
+        if !state.timeline_checked {
+            state.timeline_checked = true;
+            let device = &deps.device;
+            if device.features().timeline_semaphore && TimelineApi::for_device(device).is_some() {
+                state.timeline = Some(TimelineSemaphore::create(device, 0)?);
+            }
+        }
+
+        let id = SubmissionId(state.last_id + 1);
+        let marker = match (&state.timeline, fence) {
+            (Some(_), _) => Marker::Timeline(id.0),
+            (None, Some(fence)) => Marker::Fence(fence.clone(), false),
+            (None, None) => match state.free_fences.pop() {
+                Some(fence) => Marker::Fence(fence, true),
+                None => Marker::Fence(Fence::create(&deps.device, false)?, true),
+            },
+        };
+
+        let mut signal_values = Vec::new();
+        if let Some(timeline) = &state.timeline {
+            if raw.is_empty() {
+                raw.push(Default::default());
+            }
+            let last_signal = &mut raw.last_mut().expect("Not empty").3;
+            signal_values.resize(last_signal.len(), 0);
+            signal_values.push(id.0);
+            last_signal.push(*timeline.handle().handle());
+        }
+        let timeline_info = vk::TimelineSemaphoreSubmitInfo::builder()
+            .signal_semaphore_values(&signal_values)
+            .build();
+
+        let mut infos: Vec<_> = raw
+            .iter()
+            .map(|(wait, stages, command_buffers, signal)| {
+                vk::SubmitInfo::builder()
+                    .wait_semaphores(wait)
+                    .wait_dst_stage_mask(stages)
+                    .command_buffers(command_buffers)
+                    .signal_semaphores(signal)
+                    .build()
+            })
+            .collect();
+        if let (Some(info), false) = (infos.last_mut(), signal_values.is_empty()) {
+            info.p_next = &timeline_info as *const _ as _;
+        }
+
+        let raw_fence = match (&marker, fence) {
+            (Marker::Fence(fence, _), _) | (_, Some(fence)) => **fence,
+            _ => vk::Fence::null(),
+        };
+        let fence_lock = fence.map(|f| f.dependencies().lock());
+        let submitted = deps.device.queue_submit(**self, &infos, raw_fence);
+        drop(fence_lock);
+        if let Err(e) = submitted {
+            if let Marker::Fence(fence, true) = marker {
+                state.free_fences.push(fence);
+            }
+            return Err(e);
+        }
+        state.last_id = id.0;
+
+        let mut pending = Pending {
+            id,
+            marker,
+            _fence: fence.cloned(),
+            _command_buffers: Vec::new(),
+            _semaphores: Vec::new(),
+            _resources: Vec::new(),
+        };
+        for submission in submissions {
+            pending._command_buffers.extend(submission.command_buffers);
+            let waits = submission.wait.into_iter().map(|(sem, _)| sem);
+            pending._semaphores.extend(waits.chain(submission.signal));
+            pending._resources.extend(submission.resources);
+        }
+        state.pending.push(pending);
+
+        let completed = state.take_completed();
+        // Released handles may wait for device idle on drop.
+        drop((state, queues));
+        drop(completed?);
+        Ok(id)
+    }
+
+    /// Presents swapchain images after `wait` semaphores are signaled.
+    /// Returns `true` if any swapchain is suboptimal.
+    ///
+    /// # Safety
+    /// * `images` indices must be acquired from their swapchains and not presented since.
+    /// * images must be transitioned to `PRESENT_SRC_KHR` layout by prior submissions.
+    /// * `wait` semaphores must be signaled or have pending signal operations.
+    ///
+    /// # Panics
+    /// * if `images` is empty.
+    pub unsafe fn present(
+        &self,
+        wait: &[Semaphore],
+        images: &[(&Swapchain, u32)],
+    ) -> VkResult<bool> {
+        let loader = &images
+            .first()
+            .expect("At least one swapchain image is presented")
+            .0
+            .dependencies()
+            .loader;
+        let wait_semaphores: Vec<_> = wait.iter().map(|s| **s).collect();
+        let swapchains: Vec<_> = images.iter().map(|(s, _)| ***s).collect();
+        let indices: Vec<_> = images.iter().map(|(_, i)| *i).collect();
+        let info = vk::PresentInfoKHR::builder()
+            .wait_semaphores(&wait_semaphores)
+            .swapchains(&swapchains)
+            .image_indices(&indices);
+
+        let deps = self.dependencies();
+        let _queues = deps.device.dependencies().queue_access();
+        let _state = deps.lock();
+        loader.queue_present(**self, &info)
+    }
+
+    /// Waits for all submissions and releases handles, used by them.
+    pub fn wait_idle(&self) -> VkResult<()> {
+        let deps = self.dependencies();
+        let queues = deps.device.dependencies().queue_access();
+        let mut state = deps.lock();
+        unsafe { deps.device.queue_wait_idle(**self)? };
+        let completed = state.take_all();
+        drop((state, queues));
+        drop(completed?);
+        Ok(())
+    }
+
+    /// Releases handles, used by completed submissions.
+    /// Returns count of released submissions.
+    pub fn poll(&self) -> VkResult<usize> {
+        let completed = self.dependencies().lock().take_completed()?;
+        Ok(completed.len())
+    }
+
+    /// Checks if submission `id` is complete, releasing handles of completed submissions.
+    pub fn is_complete(&self, id: SubmissionId) -> VkResult<bool> {
+        self.poll()?;
+        Ok(!self.is_pending(id))
+    }
+
+    /// Waits for submission `id` to complete. `timeout` is in nanoseconds.
+    pub fn wait(&self, id: SubmissionId, timeout: u64) -> VkResult<WaitResult> {
+        let marker = {
+            let state = self.dependencies().lock();
+            let pending = state.pending.iter().find(|p| p.id == id);
+            match pending.map(|p| &p.marker) {
+                None => return Ok(WaitResult::Signaled),
+                Some(Marker::Fence(fence, _)) => Marker::Fence(fence.clone(), false),
+                Some(Marker::Timeline(value)) => Marker::Timeline(*value),
+            }
+        };
+
+        let result = match marker {
+            Marker::Fence(fence, _) => fence.wait(timeout)?,
+            Marker::Timeline(value) => {
+                let timeline = self.dependencies().lock().timeline.clone();
+                let timeline = timeline.expect("Timeline is created before submission");
+                timeline.wait(value, timeout)?
+            }
+        };
+        self.poll()?;
+        Ok(result)
+    }
+
+    /// Count of submissions, not observed complete yet.
+    pub fn pending_count(&self) -> usize {
+        self.dependencies().lock().pending.len()
+    }
+
+    /// Calls `f` with raw queue, while access to it is locked.
+    pub fn with_raw<R>(&self, f: impl FnOnce(vk::Queue) -> R) -> R {
+        let deps = self.dependencies();
+        let _queues = deps.device.dependencies().queue_access();
+        let _state = deps.lock();
+        f(**self)
+    }
+
+    fn is_pending(&self, id: SubmissionId) -> bool {
+        self.dependencies()
+            .lock()
+            .pending
+            .iter()
+            .any(|p| p.id == id)
+    }
+}
@@ -0,0 +1,294 @@
+use crate::device::Device;
+use crate::instance::Instance;
+use ash::version::InstanceV1_0;
+use ash::vk::{self, Handle};
+use std::backtrace::Backtrace;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+/// Device or instance, other handles are created from.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Owner {
+    Instance(vk::Instance),
+    Device(vk::Device),
+}
+
+impl Owner {
+    pub fn of_instance(instance: &Instance) -> Self {
+        Owner::Instance(instance.handle().handle())
+    }
+
+    pub fn of_device(device: &Device) -> Self {
+        Owner::Device(device.handle().handle())
+    }
+}
+
+impl fmt::Display for Owner {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Owner::Instance(instance) => write!(f, "instance {:#x}", instance.as_raw()),
+            Owner::Device(device) => write!(f, "device {:#x}", device.as_raw()),
+        }
+    }
+}
+
+/// Handle, which is not destroyed yet.
+#[derive(Debug, Clone)]
+pub struct LiveObject {
+    /// `RawHandle::name` of handle.
+    pub name: &'static str,
+    pub owner: Option<Owner>,
+    /// Captured if registry is enabled with backtraces.
+    pub backtrace: Option<Arc<Backtrace>>,
+}
+
+impl fmt::Display for LiveObject {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.owner {
+            Some(owner) => write!(f, "{} of {}", self.name, owner)?,
+            None => write!(f, "{}", self.name)?,
+        }
+        if let Some(backtrace) = &self.backtrace {
+            write!(f, ", created at:\n{}", backtrace)?;
+        }
+        Ok(())
+    }
+}
+
+/// List of live objects.
+#[derive(Debug, Clone, Default)]
+pub struct Report {
+    objects: Vec<LiveObject>,
+}
+
+impl Report {
+    /// Live objects in creation order.
+    pub fn objects(&self) -> &[LiveObject] {
+        &self.objects
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.objects.is_empty()
+    }
+
+    /// Count of live objects with `name`.
+    pub fn count(&self, name: &str) -> usize {
+        self.objects.iter().filter(|o| o.name == name).count()
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} live objects", self.objects.len())?;
+        for object in &self.objects {
+            write!(f, "\n  {}", object)?;
+        }
+        Ok(())
+    }
+}
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static CAPTURE_BACKTRACES: AtomicBool = AtomicBool::new(false);
+static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+static LIVE: Mutex<BTreeMap<u64, LiveObject>> = Mutex::new(BTreeMap::new());
+
+/// Starts recording of created handles. Handles, created before, are not tracked.
+///
+/// Recorded handle stays in registry until destruction. Handles keep their device or instance
+/// alive, so leaks are found by checking `report` or `report_for` at points, where all handles
+/// are expected to be destroyed, e.g. at the end of a test.
+pub fn enable(capture_backtraces: bool) {
+    CAPTURE_BACKTRACES.store(capture_backtraces, Ordering::Relaxed);
+    ENABLED.store(true, Ordering::Release);
+}
+
+/// Stops recording of created handles. Already tracked handles stay in registry until destroyed.
+pub fn disable() {
+    ENABLED.store(false, Ordering::Release);
+}
+
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Acquire)
+}
+
+/// All tracked live objects.
+pub fn report() -> Report {
+    collect(|_| true)
+}
+
+/// Tracked live objects, created from `owner`.
+pub fn report_for(owner: Owner) -> Report {
+    collect(|o| o.owner == Some(owner))
+}
+
+fn collect(filter: impl Fn(&LiveObject) -> bool) -> Report {
+    let objects = lock().values().filter(|o| filter(o)).cloned().collect();
+    Report { objects }
+}
+
+fn lock() -> MutexGuard<'static, BTreeMap<u64, LiveObject>> {
+    LIVE.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+pub(crate) fn register(name: &'static str, owner: impl FnOnce() -> Option<Owner>) -> Option<u64> {
+    if !is_enabled() {
+        return None;
+    }
+
+    let backtrace = match CAPTURE_BACKTRACES.load(Ordering::Relaxed) {
+        true => Some(Arc::new(Backtrace::force_capture())),
+        false => None,
+    };
+    let object = LiveObject {
+        name,
+        owner: owner(),
+        backtrace,
+    };
+
+    let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+    lock().insert(id, object);
+    Some(id)
+}
+
+pub(crate) fn unregister(id: u64) {
+    lock().remove(&id);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RawHandle;
+
+    struct Mock;
+
+    impl RawHandle for Mock {
+        type Dependencies = Owner;
+
+        fn name() -> &'static str {
+            "mock"
+        }
+
+        fn owner(deps: &Self::Dependencies) -> Option<Owner> {
+            Some(*deps)
+        }
+
+        fn destroy(&self, _: &Self::Dependencies) {}
+    }
+
+    type MockHandle = crate::Handle<Mock, Owner>;
+
+    /// Registry is global, so tests, changing its state, must not run in parallel.
+    static SERIAL: Mutex<()> = Mutex::new(());
+    static NEXT_OWNER: AtomicU64 = AtomicU64::new(1);
+
+    fn serial() -> MutexGuard<'static, ()> {
+        SERIAL.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Owner, not used by other tests, so reports of tests don't mix.
+    fn unique_owner() -> Owner {
+        let raw = NEXT_OWNER.fetch_add(1, Ordering::Relaxed);
+        Owner::Device(vk::Device::from_raw(raw))
+    }
+
+    fn mock(owner: Owner) -> MockHandle {
+        unsafe { MockHandle::new(Mock, owner) }
+    }
+
+    #[test]
+    fn registers_handle_until_drop() {
+        let _serial = serial();
+        enable(false);
+        let owner = unique_owner();
+
+        let handle = mock(owner);
+        let report = report_for(owner);
+        assert_eq!(report.count("mock"), 1);
+        assert_eq!(report.objects()[0].owner, Some(owner));
+        assert!(report.objects()[0].backtrace.is_none());
+
+        drop(handle);
+        assert!(report_for(owner).is_empty());
+    }
+
+    #[test]
+    fn clones_are_registered_once() {
+        let _serial = serial();
+        enable(false);
+        let owner = unique_owner();
+
+        let handle = mock(owner);
+        let clone = handle.clone();
+        assert_eq!(report_for(owner).count("mock"), 1);
+
+        drop(handle);
+        assert_eq!(report_for(owner).count("mock"), 1);
+        drop(clone);
+        assert!(report_for(owner).is_empty());
+    }
+
+    #[test]
+    fn unwrapped_handle_is_unregistered() {
+        let _serial = serial();
+        enable(false);
+        let owner = unique_owner();
+
+        let handle = mock(owner);
+        assert!(handle.try_unwrap().is_ok());
+        assert!(report_for(owner).is_empty());
+    }
+
+    #[test]
+    fn disabled_registry_does_not_track() {
+        let _serial = serial();
+        disable();
+        let owner = unique_owner();
+
+        let handle = mock(owner);
+        assert!(report_for(owner).is_empty());
+
+        enable(false);
+        drop(handle);
+        assert!(report_for(owner).is_empty());
+    }
+
+    #[test]
+    fn handles_of_other_owners_are_not_reported() {
+        let _serial = serial();
+        enable(false);
+        let owner = unique_owner();
+        let other = unique_owner();
+
+        let _handle = mock(owner);
+        let _other_handles = [mock(other), mock(other)];
+        assert_eq!(report_for(owner).count("mock"), 1);
+        assert_eq!(report_for(other).count("mock"), 2);
+        assert!(report().count("mock") >= 3);
+    }
+
+    #[test]
+    fn captures_backtraces_when_requested() {
+        let _serial = serial();
+        enable(true);
+        let owner = unique_owner();
+
+        let _handle = mock(owner);
+        let report = report_for(owner);
+        enable(false);
+        assert!(report.objects()[0].backtrace.is_some());
+    }
+
+    #[test]
+    fn report_lists_objects() {
+        let _serial = serial();
+        enable(false);
+        let owner = unique_owner();
+
+        let _handle = mock(owner);
+        let text = report_for(owner).to_string();
+        assert!(text.starts_with("1 live objects"));
+        assert!(text.contains(&format!("mock of {}", owner)));
+    }
+}
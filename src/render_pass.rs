@@ -2,9 +2,131 @@ use crate::device::Device;
 use crate::{Handle, RawHandle};
 use ash::version::DeviceV1_0;
 use ash::vk;
+use std::error::Error;
+use std::fmt;
+use std::hash::Hash;
+
+#[derive(Debug, Clone, Default)]
+pub struct SubpassDesc {
+    pub flags: vk::SubpassDescriptionFlags,
+    pub pipeline_bind_point: vk::PipelineBindPoint,
+    pub input_attachments: Vec<vk::AttachmentReference>,
+    pub color_attachments: Vec<vk::AttachmentReference>,
+    /// Empty or of the same length as `color_attachments`.
+    pub resolve_attachments: Vec<vk::AttachmentReference>,
+    pub depth_stencil_attachment: Option<vk::AttachmentReference>,
+    pub preserve_attachments: Vec<u32>,
+}
+
+impl SubpassDesc {
+    /// Resolve attachments are set only if there are as many of them as color attachments.
+    pub fn description(&self) -> vk::SubpassDescriptionBuilder<'_> {
+        let mut builder = vk::SubpassDescription::builder()
+            .flags(self.flags)
+            .pipeline_bind_point(self.pipeline_bind_point)
+            .input_attachments(&self.input_attachments)
+            .color_attachments(&self.color_attachments)
+            .preserve_attachments(&self.preserve_attachments);
+
+        // Builder setter would replace color attachment count with resolve attachment count.
+        if self.has_valid_resolve_count() && !self.resolve_attachments.is_empty() {
+            builder.p_resolve_attachments = self.resolve_attachments.as_ptr();
+        }
+
+        if let Some(depth_stencil) = &self.depth_stencil_attachment {
+            builder = builder.depth_stencil_attachment(depth_stencil);
+        }
+
+        builder
+    }
+
+    fn has_valid_resolve_count(&self) -> bool {
+        self.resolve_attachments.is_empty()
+            || self.resolve_attachments.len() == self.color_attachments.len()
+    }
+
+    fn key(&self) -> impl Eq + Hash {
+        (
+            self.flags,
+            self.pipeline_bind_point,
+            references_key(&self.input_attachments),
+            references_key(&self.color_attachments),
+            references_key(&self.resolve_attachments),
+            self.depth_stencil_attachment.as_ref().map(reference_key),
+            self.preserve_attachments.clone(),
+        )
+    }
+}
+
+impl_eq_hash_by_key!(SubpassDesc);
+
+fn reference_key(reference: &vk::AttachmentReference) -> (u32, vk::ImageLayout) {
+    (reference.attachment, reference.layout)
+}
+
+fn references_key(references: &[vk::AttachmentReference]) -> Vec<(u32, vk::ImageLayout)> {
+    references.iter().map(reference_key).collect()
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct RenderPassDesc {
+    pub flags: vk::RenderPassCreateFlags,
+    pub attachments: Vec<vk::AttachmentDescription>,
+    pub subpasses: Vec<SubpassDesc>,
+    pub dependencies: Vec<vk::SubpassDependency>,
+}
+
+impl RenderPassDesc {
+    /// Subpass descriptions, referencing data of `self`.
+    pub(crate) fn subpass_descriptions(&self) -> Vec<vk::SubpassDescription> {
+        self.subpasses
+            .iter()
+            .map(|subpass| subpass.description().build())
+            .collect()
+    }
+
+    fn key(&self) -> impl Eq + Hash + '_ {
+        let attachments: Vec<_> = self
+            .attachments
+            .iter()
+            .map(|a| {
+                (
+                    a.flags,
+                    a.format,
+                    a.samples,
+                    a.load_op,
+                    a.store_op,
+                    a.stencil_load_op,
+                    a.stencil_store_op,
+                    a.initial_layout,
+                    a.final_layout,
+                )
+            })
+            .collect();
+        let dependencies: Vec<_> = self
+            .dependencies
+            .iter()
+            .map(|d| {
+                (
+                    d.src_subpass,
+                    d.dst_subpass,
+                    d.src_stage_mask,
+                    d.dst_stage_mask,
+                    d.src_access_mask,
+                    d.dst_access_mask,
+                    d.dependency_flags,
+                )
+            })
+            .collect();
+        (self.flags, attachments, &self.subpasses, dependencies)
+    }
+}
+
+impl_eq_hash_by_key!(RenderPassDesc);
 
 pub struct Deps {
     pub device: Device,
+    pub desc: RenderPassDesc,
 }
 
 impl RawHandle for vk::RenderPass {
@@ -14,9 +136,91 @@ impl RawHandle for vk::RenderPass {
         "render pass"
     }
 
+    fn device(deps: &Self::Dependencies) -> Option<&Device> {
+        Some(&deps.device)
+    }
+
+    fn object_type() -> vk::ObjectType {
+        vk::ObjectType::RENDER_PASS
+    }
+
+    fn raw_handles(&self) -> Vec<u64> {
+        vec![vk::Handle::as_raw(*self)]
+    }
+
     fn destroy(&self, dependencies: &Self::Dependencies) {
         unsafe { dependencies.device.destroy_render_pass(*self, None) }
     }
 }
 
 pub type RenderPass = Handle<vk::RenderPass, Deps>;
+
+impl RenderPass {
+    pub fn create(device: &Device, desc: &RenderPassDesc) -> Result<Self, CreateError> {
+        for (index, subpass) in desc.subpasses.iter().enumerate() {
+            if !subpass.has_valid_resolve_count() {
+                return Err(CreateError::ResolveAttachmentCount {
+                    subpass: index,
+                    color: subpass.color_attachments.len(),
+                    resolve: subpass.resolve_attachments.len(),
+                });
+            }
+        }
+
+        let subpasses = desc.subpass_descriptions();
+        let ci = vk::RenderPassCreateInfo::builder()
+            .flags(desc.flags)
+            .attachments(&desc.attachments)
+            .subpasses(&subpasses)
+            .dependencies(&desc.dependencies);
+
+        unsafe {
+            let raw = device.create_render_pass(&ci, None)?;
+            let deps = Deps {
+                device: device.clone(),
+                desc: desc.clone(),
+            };
+            Ok(Self::new(raw, deps))
+        }
+    }
+
+    pub fn desc(&self) -> &RenderPassDesc {
+        &self.dependencies().desc
+    }
+}
+
+#[derive(Debug)]
+pub enum CreateError {
+    /// Subpass resolve attachments are neither empty nor of color attachments count.
+    ResolveAttachmentCount {
+        subpass: usize,
+        color: usize,
+        resolve: usize,
+    },
+    Vulkan(vk::Result),
+}
+
+impl From<vk::Result> for CreateError {
+    fn from(e: vk::Result) -> Self {
+        Self::Vulkan(e)
+    }
+}
+
+impl Error for CreateError {}
+
+impl fmt::Display for CreateError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CreateError::ResolveAttachmentCount {
+                subpass,
+                color,
+                resolve,
+            } => write!(
+                f,
+                "Subpass {} has {} resolve attachments for {} color attachments",
+                subpass, resolve, color
+            ),
+            CreateError::Vulkan(e) => write!(f, "Can't create render pass: {}", e),
+        }
+    }
+}
@@ -1,10 +1,84 @@
 use crate::device::Device;
 use crate::{Handle, RawHandle};
+use ash::prelude::VkResult;
 use ash::version::DeviceV1_0;
 use ash::vk;
+use std::hash::Hash;
+
+/// Sampler parameters. Floating point fields are compared and hashed bitwise.
+#[derive(Debug, Copy, Clone, Default)]
+pub struct SamplerDesc {
+    pub flags: vk::SamplerCreateFlags,
+    pub mag_filter: vk::Filter,
+    pub min_filter: vk::Filter,
+    pub mipmap_mode: vk::SamplerMipmapMode,
+    pub address_mode_u: vk::SamplerAddressMode,
+    pub address_mode_v: vk::SamplerAddressMode,
+    pub address_mode_w: vk::SamplerAddressMode,
+    pub mip_lod_bias: f32,
+    pub anisotropy_enable: bool,
+    pub max_anisotropy: f32,
+    pub compare_enable: bool,
+    pub compare_op: vk::CompareOp,
+    pub min_lod: f32,
+    pub max_lod: f32,
+    pub border_color: vk::BorderColor,
+    pub unnormalized_coordinates: bool,
+}
+
+impl SamplerDesc {
+    pub fn create_info(&self) -> vk::SamplerCreateInfoBuilder<'static> {
+        vk::SamplerCreateInfo::builder()
+            .flags(self.flags)
+            .mag_filter(self.mag_filter)
+            .min_filter(self.min_filter)
+            .mipmap_mode(self.mipmap_mode)
+            .address_mode_u(self.address_mode_u)
+            .address_mode_v(self.address_mode_v)
+            .address_mode_w(self.address_mode_w)
+            .mip_lod_bias(self.mip_lod_bias)
+            .anisotropy_enable(self.anisotropy_enable)
+            .max_anisotropy(self.max_anisotropy)
+            .compare_enable(self.compare_enable)
+            .compare_op(self.compare_op)
+            .min_lod(self.min_lod)
+            .max_lod(self.max_lod)
+            .border_color(self.border_color)
+            .unnormalized_coordinates(self.unnormalized_coordinates)
+    }
+
+    fn key(&self) -> impl Eq + Hash {
+        (
+            self.flags,
+            [self.mag_filter, self.min_filter],
+            self.mipmap_mode,
+            [
+                self.address_mode_u,
+                self.address_mode_v,
+                self.address_mode_w,
+            ],
+            [
+                self.mip_lod_bias.to_bits(),
+                self.max_anisotropy.to_bits(),
+                self.min_lod.to_bits(),
+                self.max_lod.to_bits(),
+            ],
+            [
+                self.anisotropy_enable,
+                self.compare_enable,
+                self.unnormalized_coordinates,
+            ],
+            self.compare_op,
+            self.border_color,
+        )
+    }
+}
+
+impl_eq_hash_by_key!(SamplerDesc);
 
 pub struct Deps {
     pub device: Device,
+    pub desc: SamplerDesc,
 }
 
 impl RawHandle for vk::Sampler {
@@ -14,9 +88,38 @@ impl RawHandle for vk::Sampler {
         "sampler"
     }
 
+    fn device(deps: &Self::Dependencies) -> Option<&Device> {
+        Some(&deps.device)
+    }
+
+    fn object_type() -> vk::ObjectType {
+        vk::ObjectType::SAMPLER
+    }
+
+    fn raw_handles(&self) -> Vec<u64> {
+        vec![vk::Handle::as_raw(*self)]
+    }
+
     fn destroy(&self, dependencies: &Self::Dependencies) {
         unsafe { dependencies.device.destroy_sampler(*self, None) }
     }
 }
 
 pub type Sampler = Handle<vk::Sampler, Deps>;
+
+impl Sampler {
+    pub fn create(device: &Device, desc: &SamplerDesc) -> VkResult<Self> {
+        unsafe {
+            let raw = device.create_sampler(&desc.create_info(), None)?;
+            let deps = Deps {
+                device: device.clone(),
+                desc: *desc,
+            };
+            Ok(Self::new(raw, deps))
+        }
+    }
+
+    pub fn desc(&self) -> &SamplerDesc {
+        &self.dependencies().desc
+    }
+}
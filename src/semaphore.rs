@@ -0,0 +1,210 @@
+use crate::device::Device;
+use crate::features;
+use crate::{Handle, RawHandle, WaitResult};
+use ash::extensions::khr;
+use ash::prelude::VkResult;
+use ash::version::{DeviceV1_0, DeviceV1_2};
+use ash::vk;
+
+pub struct Deps {
+    pub device: Device,
+}
+
+impl RawHandle for vk::Semaphore {
+    type Dependencies = Deps;
+
+    fn name() -> &'static str {
+        "semaphore"
+    }
+
+    fn device(deps: &Self::Dependencies) -> Option<&Device> {
+        Some(&deps.device)
+    }
+
+    fn object_type() -> vk::ObjectType {
+        vk::ObjectType::SEMAPHORE
+    }
+
+    fn raw_handles(&self) -> Vec<u64> {
+        vec![vk::Handle::as_raw(*self)]
+    }
+
+    fn destroy(&self, dependencies: &Self::Dependencies) {
+        unsafe { dependencies.device.destroy_semaphore(*self, None) }
+    }
+}
+
+pub type Semaphore = Handle<vk::Semaphore, Deps>;
+
+impl Semaphore {
+    pub fn create(device: &Device) -> VkResult<Self> {
+        let ci = vk::SemaphoreCreateInfo::builder();
+        unsafe {
+            let raw = device.create_semaphore(&ci, None)?;
+            let deps = Deps {
+                device: device.clone(),
+            };
+            Ok(Self::new(raw, deps))
+        }
+    }
+}
+
+/// Functions used to operate timeline semaphores.
+#[derive(Clone)]
+pub enum TimelineApi {
+    /// Vulkan 1.2 core functions.
+    Core,
+    /// VK_KHR_timeline_semaphore extension functions.
+    Khr(khr::TimelineSemaphore),
+}
+
+impl TimelineApi {
+    /// `Core` if `timeline_semaphore` feature is enabled on Vulkan 1.2 `device`,
+    /// `Khr` if VK_KHR_timeline_semaphore extension is enabled.
+    pub fn for_device(device: &Device) -> Option<Self> {
+        let deps = device.dependencies();
+        let api_version = unsafe { features::api_version(&deps.instance, deps.pdevice) };
+        if device.features().timeline_semaphore && api_version >= vk::API_VERSION_1_2 {
+            return Some(TimelineApi::Core);
+        }
+
+        if !deps.is_extension_enabled(khr::TimelineSemaphore::name()) {
+            return None;
+        }
+        let instance = &deps.instance;
+        let entry = &instance.dependencies().entry;
+        Some(TimelineApi::Khr(khr::TimelineSemaphore::new(
+            entry,
+            &**instance,
+        )))
+    }
+}
+
+/// Semaphore, created with `vk::SemaphoreType::TIMELINE`.
+pub struct RawTimelineSemaphore {
+    handle: vk::Semaphore,
+}
+
+impl RawTimelineSemaphore {
+    pub fn new(handle: vk::Semaphore) -> Self {
+        Self { handle }
+    }
+
+    pub fn handle(&self) -> &vk::Semaphore {
+        &self.handle
+    }
+}
+
+pub struct TimelineDeps {
+    pub device: Device,
+    pub api: TimelineApi,
+}
+
+impl RawHandle for RawTimelineSemaphore {
+    type Dependencies = TimelineDeps;
+
+    fn name() -> &'static str {
+        "timeline semaphore"
+    }
+
+    fn device(deps: &Self::Dependencies) -> Option<&Device> {
+        Some(&deps.device)
+    }
+
+    fn object_type() -> vk::ObjectType {
+        vk::ObjectType::SEMAPHORE
+    }
+
+    fn raw_handles(&self) -> Vec<u64> {
+        vec![vk::Handle::as_raw(self.handle)]
+    }
+
+    fn destroy(&self, deps: &Self::Dependencies) {
+        unsafe { deps.device.destroy_semaphore(self.handle, None) }
+    }
+}
+
+pub type TimelineSemaphore = Handle<RawTimelineSemaphore, TimelineDeps>;
+
+impl TimelineSemaphore {
+    /// Creates timeline semaphore with counter set to `initial_value`.
+    /// Functions are chosen by `TimelineApi::for_device`.
+    ///
+    /// Fails with `vk::Result::ERROR_FEATURE_NOT_PRESENT` if `timeline_semaphore` feature is not
+    /// enabled on `device` and with `vk::Result::ERROR_EXTENSION_NOT_PRESENT` if neither
+    /// Vulkan 1.2 nor VK_KHR_timeline_semaphore extension is available.
+    pub fn create(device: &Device, initial_value: u64) -> VkResult<Self> {
+        if !device.features().timeline_semaphore {
+            return Err(vk::Result::ERROR_FEATURE_NOT_PRESENT);
+        }
+        let api = TimelineApi::for_device(device).ok_or(vk::Result::ERROR_EXTENSION_NOT_PRESENT)?;
+
+        let mut type_ci = vk::SemaphoreTypeCreateInfo::builder()
+            .semaphore_type(vk::SemaphoreType::TIMELINE)
+            .initial_value(initial_value);
+        let ci = vk::SemaphoreCreateInfo::builder().push_next(&mut type_ci);
+
+        unsafe {
+            let raw = device.create_semaphore(&ci, None)?;
+            let deps = TimelineDeps {
+                device: device.clone(),
+                api,
+            };
+            Ok(Self::new(RawTimelineSemaphore::new(raw), deps))
+        }
+    }
+
+    /// Sets counter of semaphore to `value` from host.
+    pub fn signal(&self, value: u64) -> VkResult<()> {
+        let info = vk::SemaphoreSignalInfo::builder()
+            .semaphore(*self.handle().handle())
+            .value(value);
+        let device = &self.dependencies().device;
+        unsafe {
+            match &self.dependencies().api {
+                TimelineApi::Core => device.signal_semaphore(&info),
+                TimelineApi::Khr(loader) => {
+                    loader.signal_semaphore(device.handle().handle(), &info)
+                }
+            }
+        }
+    }
+
+    /// Waits until counter of semaphore reaches `value`. `timeout` is in nanoseconds.
+    pub fn wait(&self, value: u64, timeout: u64) -> VkResult<WaitResult> {
+        let semaphores = [*self.handle().handle()];
+        let values = [value];
+        let info = vk::SemaphoreWaitInfo::builder()
+            .semaphores(&semaphores)
+            .values(&values);
+        let device = &self.dependencies().device;
+        let result = unsafe {
+            match &self.dependencies().api {
+                TimelineApi::Core => device.wait_semaphores(&info, timeout),
+                TimelineApi::Khr(loader) => {
+                    loader.wait_semaphores(device.handle().handle(), &info, timeout)
+                }
+            }
+        };
+
+        match result {
+            Ok(()) => Ok(WaitResult::Signaled),
+            Err(vk::Result::TIMEOUT) => Ok(WaitResult::Timeout),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Current counter value of semaphore.
+    pub fn counter_value(&self) -> VkResult<u64> {
+        let semaphore = *self.handle().handle();
+        let device = &self.dependencies().device;
+        unsafe {
+            match &self.dependencies().api {
+                TimelineApi::Core => device.get_semaphore_counter_value(semaphore),
+                TimelineApi::Khr(loader) => {
+                    loader.get_semaphore_counter_value(device.handle().handle(), semaphore)
+                }
+            }
+        }
+    }
+}
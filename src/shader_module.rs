@@ -1,5 +1,6 @@
 use crate::device::Device;
 use crate::{Handle, RawHandle};
+use ash::prelude::VkResult;
 use ash::version::DeviceV1_0;
 use ash::vk;
 
@@ -14,9 +15,35 @@ impl RawHandle for vk::ShaderModule {
         "shader module"
     }
 
+    fn device(deps: &Self::Dependencies) -> Option<&Device> {
+        Some(&deps.device)
+    }
+
+    fn object_type() -> vk::ObjectType {
+        vk::ObjectType::SHADER_MODULE
+    }
+
+    fn raw_handles(&self) -> Vec<u64> {
+        vec![vk::Handle::as_raw(*self)]
+    }
+
     fn destroy(&self, dependencies: &Self::Dependencies) {
         unsafe { dependencies.device.destroy_shader_module(*self, None) }
     }
 }
 
 pub type ShaderModule = Handle<vk::ShaderModule, Deps>;
+
+impl ShaderModule {
+    /// Creates shader module from SPIR-V `code`.
+    pub fn create(device: &Device, code: &[u32]) -> VkResult<Self> {
+        let ci = vk::ShaderModuleCreateInfo::builder().code(code);
+        unsafe {
+            let raw = device.create_shader_module(&ci, None)?;
+            let deps = Deps {
+                device: device.clone(),
+            };
+            Ok(Self::new(raw, deps))
+        }
+    }
+}
@@ -1,4 +1,5 @@
 use crate::instance::Instance;
+use crate::registry::Owner;
 use crate::{Handle, RawHandle};
 use ash::extensions::khr;
 use ash::version::EntryV1_0;
@@ -17,6 +18,18 @@ impl RawHandle for vk::SurfaceKHR {
         "surface"
     }
 
+    fn owner(deps: &Self::Dependencies) -> Option<Owner> {
+        Some(Owner::of_instance(&deps.instance))
+    }
+
+    fn object_type() -> vk::ObjectType {
+        vk::ObjectType::SURFACE_KHR
+    }
+
+    fn raw_handles(&self) -> Vec<u64> {
+        vec![vk::Handle::as_raw(*self)]
+    }
+
     fn destroy(&self, deps: &Self::Dependencies) {
         unsafe { deps.loader.destroy_surface(*self, None) }
     }
@@ -57,13 +70,13 @@ pub fn enumerate_surface_extensions(
         required_extensions.extend(supported_linux_extensions);
     }
 
-    #[cfg(any(target_os = "android"))]
+    #[cfg(target_os = "android")]
     required_extensions.push(khr::AndroidSurface::name());
 
-    #[cfg(any(target_os = "macos"))]
+    #[cfg(target_os = "macos")]
     required_extensions.push(ash::ext::MetalSurface::name());
 
-    #[cfg(any(target_os = "ios"))]
+    #[cfg(target_os = "ios")]
     required_extensions.push(ash::ext::MetalSurface::name());
 
     unsafe {
@@ -1,13 +1,51 @@
-use crate::{Handle, RawHandle};
-use ash::extensions::khr;
-use ash::vk;
 use crate::device::Device;
 use crate::surface::Surface;
+use crate::{Handle, RawHandle, Sharing};
+use ash::extensions::khr;
+use ash::prelude::VkResult;
+use ash::vk;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct SwapchainDesc {
+    pub flags: vk::SwapchainCreateFlagsKHR,
+    pub min_image_count: u32,
+    pub image_format: vk::Format,
+    pub image_color_space: vk::ColorSpaceKHR,
+    pub image_extent: vk::Extent2D,
+    pub image_array_layers: u32,
+    pub image_usage: vk::ImageUsageFlags,
+    pub image_sharing: Sharing,
+    pub pre_transform: vk::SurfaceTransformFlagsKHR,
+    pub composite_alpha: vk::CompositeAlphaFlagsKHR,
+    pub present_mode: vk::PresentModeKHR,
+    pub clipped: bool,
+}
+
+impl SwapchainDesc {
+    pub fn create_info(&self, surface: vk::SurfaceKHR) -> vk::SwapchainCreateInfoKHRBuilder<'_> {
+        vk::SwapchainCreateInfoKHR::builder()
+            .flags(self.flags)
+            .surface(surface)
+            .min_image_count(self.min_image_count)
+            .image_format(self.image_format)
+            .image_color_space(self.image_color_space)
+            .image_extent(self.image_extent)
+            .image_array_layers(self.image_array_layers)
+            .image_usage(self.image_usage)
+            .image_sharing_mode(self.image_sharing.mode())
+            .queue_family_indices(self.image_sharing.queue_family_indices())
+            .pre_transform(self.pre_transform)
+            .composite_alpha(self.composite_alpha)
+            .present_mode(self.present_mode)
+            .clipped(self.clipped)
+    }
+}
 
 pub struct Deps {
     pub loader: khr::Swapchain,
     pub device: Device,
     pub surface: Surface,
+    pub desc: SwapchainDesc,
 }
 
 impl RawHandle for vk::SwapchainKHR {
@@ -17,9 +55,42 @@ impl RawHandle for vk::SwapchainKHR {
         "swapchain"
     }
 
+    fn device(deps: &Self::Dependencies) -> Option<&Device> {
+        Some(&deps.device)
+    }
+
+    fn object_type() -> vk::ObjectType {
+        vk::ObjectType::SWAPCHAIN_KHR
+    }
+
+    fn raw_handles(&self) -> Vec<u64> {
+        vec![vk::Handle::as_raw(*self)]
+    }
+
     fn destroy(&self, deps: &Self::Dependencies) {
         unsafe { deps.loader.destroy_swapchain(*self, None) }
     }
 }
 
 pub type Swapchain = Handle<vk::SwapchainKHR, Deps>;
+
+impl Swapchain {
+    pub fn create(device: &Device, surface: &Surface, desc: &SwapchainDesc) -> VkResult<Self> {
+        let instance = &device.dependencies().instance;
+        let loader = khr::Swapchain::new(instance.handle(), device.handle());
+        unsafe {
+            let raw = loader.create_swapchain(&desc.create_info(**surface), None)?;
+            let deps = Deps {
+                loader,
+                device: device.clone(),
+                surface: surface.clone(),
+                desc: desc.clone(),
+            };
+            Ok(Self::new(raw, deps))
+        }
+    }
+
+    pub fn desc(&self) -> &SwapchainDesc {
+        &self.dependencies().desc
+    }
+}